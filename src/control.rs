@@ -0,0 +1,184 @@
+/// The control view is the terminal of the presenter, while the audience follows the deck in
+/// another terminal. It shows the current and the next slide side by side, the speaker notes
+/// below them, and a header with the position in the deck, the elapsed time and the clock.
+use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use colored::*;
+
+use crate::utils::{clip_line, strip_ansi_codes, visible_width, wrap_text};
+
+/// What the control view shows.
+pub struct ControlView<'a> {
+    /// The current slide, laid out for its preview.
+    pub current: &'a str,
+    /// The next slide, laid out for its preview, None on the last slide.
+    pub next: Option<&'a str>,
+    pub notes: &'a str,
+    /// The number of the current slide, starting at 1.
+    pub position: u32,
+    pub total: usize,
+    pub title: Option<&'a str>,
+    /// When the presentation started.
+    pub started: Instant,
+}
+
+/// The layout of the control view in a terminal of the given size.
+pub struct Layout {
+    /// The width of each preview.
+    pub preview_width: u16,
+    /// The height of each preview, without its label.
+    pub preview_height: u16,
+}
+
+impl Layout {
+    /// The previews take three fifths of the terminal, below the header, and the notes the rest.
+    pub fn new(width: u16, height: u16) -> Layout {
+        Layout {
+            preview_width: (width.saturating_sub(1) / 2).max(1),
+            preview_height: (height.saturating_sub(1) * 3 / 5).saturating_sub(1).max(1),
+        }
+    }
+}
+
+/// Draw the whole control view.
+/// # Arguments
+/// * `out` - The terminal to draw to.
+/// * `view` - What the view shows.
+/// * `width` - The width of the terminal.
+/// * `height` - The height of the terminal.
+pub fn draw<W: Write>(out: &mut W, view: &ControlView, width: u16, height: u16) -> io::Result<()> {
+    let layout = Layout::new(width, height);
+    write!(out, "{}", termion::clear::All)?;
+    draw_header(out, view, width)?;
+
+    // The labels of the previews
+    let right = layout.preview_width + 2;
+    write!(
+        out,
+        "{}{}{}{}",
+        termion::cursor::Goto(1, 2),
+        "current".dimmed(),
+        termion::cursor::Goto(right, 2),
+        "next".dimmed()
+    )?;
+
+    let current: Vec<&str> = view.current.lines().collect();
+    let next: Vec<&str> = view.next.unwrap_or("").lines().collect();
+    for row in 0..layout.preview_height {
+        let top = 3 + row;
+        if let Some(line) = current.get(row as usize) {
+            let line = clip_line(line, layout.preview_width as usize, "");
+            write!(out, "{}{}\x1b[0m", termion::cursor::Goto(1, top), line)?;
+        }
+        write!(
+            out,
+            "{}{}",
+            termion::cursor::Goto(layout.preview_width + 1, top),
+            "│".dimmed()
+        )?;
+        if let Some(line) = next.get(row as usize) {
+            let line = clip_line(line, (width - right + 1) as usize, "");
+            write!(out, "{}{}\x1b[0m", termion::cursor::Goto(right, top), line)?;
+        }
+    }
+    if view.next.is_none() {
+        write!(
+            out,
+            "{}{}",
+            termion::cursor::Goto(right, 3),
+            "End of the deck".dimmed()
+        )?;
+    }
+
+    // The notes take the rest of the terminal
+    let notes_top = 3 + layout.preview_height;
+    let title = "── notes ";
+    write!(
+        out,
+        "{}{}",
+        termion::cursor::Goto(1, notes_top),
+        format!(
+            "{}{}",
+            title,
+            "─".repeat((width as usize).saturating_sub(title.chars().count()))
+        )
+        .dimmed()
+    )?;
+    let notes = if view.notes.trim().is_empty() {
+        vec!["No notes for this slide".dimmed().to_string()]
+    } else {
+        wrap_text(&strip_ansi_codes(view.notes), width as usize, "")
+            .lines()
+            .map(|line| line.yellow().to_string())
+            .collect()
+    };
+    for (row, line) in notes
+        .iter()
+        .take(height.saturating_sub(notes_top) as usize)
+        .enumerate()
+    {
+        write!(
+            out,
+            "{}{}",
+            termion::cursor::Goto(1, notes_top + 1 + row as u16),
+            line
+        )?;
+    }
+
+    out.flush()
+}
+
+/// Draw the header, with the position in the deck, the elapsed time and the clock.
+/// It is drawn on its own every second.
+pub fn draw_header<W: Write>(out: &mut W, view: &ControlView, width: u16) -> io::Result<()> {
+    let position = match view.title {
+        Some(title) => format!(" {}/{} · {}", view.position, view.total, title),
+        None => format!(" {}/{}", view.position, view.total),
+    };
+    let times = format!(
+        "elapsed {} · {} ",
+        format_duration(view.started.elapsed()),
+        clock()
+    );
+    let fill = (width as usize).saturating_sub(visible_width(&position) + visible_width(&times));
+    let header = clip_line(
+        &format!("{}{}{}", position, " ".repeat(fill), times),
+        width as usize,
+        "",
+    );
+    write!(
+        out,
+        "{}{}{}",
+        termion::cursor::Goto(1, 1),
+        termion::clear::CurrentLine,
+        strip_ansi_codes(&header).black().on_white()
+    )?;
+    out.flush()
+}
+
+/// Formats a duration as hours, minutes and seconds.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Returns the time of the day, in the local time zone.
+fn clock() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as libc::time_t;
+    // The offset of the local time zone is only known to the C library
+    let mut local: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut local) };
+    format!(
+        "{:02}:{:02}:{:02}",
+        local.tm_hour, local.tm_min, local.tm_sec
+    )
+}
@@ -0,0 +1,526 @@
+/// A deck is the ordered list of slides of a project.
+/// The slides are either listed in a `deck.yml` manifest, numbered files (`1.md`, `2.md`, ...) in
+/// the project directory, or a single markdown file split into slides on a separator line, such
+/// as `---` or `<!-- slide -->`.
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use markdown::mdast;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::utils::ALIGNMENT_FLAG;
+
+/// The separator used to split a single-file deck when the style does not set one.
+pub const DEFAULT_SEPARATOR: &str = "---";
+
+/// The file a project directory is presented from when it has no numbered slides.
+pub const DECK_FILE: &str = "deck.md";
+
+/// The manifest listing the slides of a deck, in order.
+pub const MANIFEST_FILE: &str = "deck.yml";
+
+lazy_static! {
+    /// Matches the notes written in a comment, `<!-- notes: ... -->`
+    static ref NOTES_COMMENT: Regex = Regex::new(r"(?s)<!--\s*notes:(.*?)-->").unwrap();
+    /// Matches the `<!-- incremental -->` directive, which reveals the items of the lists of a slide one at a time
+    static ref INCREMENTAL: Regex = Regex::new(r"^<!--\s*incremental\s*-->$").unwrap();
+}
+
+/// A single slide of a deck.
+#[derive(Clone)]
+pub struct Slide {
+    /// The file the slide was read from.
+    pub path: PathBuf,
+    /// The markdown contents of the slide, without its notes.
+    pub contents: String,
+    /// The title of the slide, when the manifest sets one.
+    pub title: Option<String>,
+    /// The speaker notes of the slide, in markdown. Empty when the slide has none.
+    pub notes: String,
+    /// Whether the items of the lists of the slide are revealed one at a time, set in the manifest
+    /// or with an `<!-- incremental -->` line in the slide.
+    pub incremental: bool,
+}
+
+impl Slide {
+    /// Creates a slide, and takes the speaker notes and the `<!-- incremental -->` directive out of its contents.
+    pub fn new(path: PathBuf, contents: &str, title: Option<String>) -> Slide {
+        let (contents, notes) = split_notes(contents);
+        let (contents, incremental) = split_incremental(&contents);
+        Slide {
+            path,
+            contents,
+            title,
+            notes,
+            incremental,
+        }
+    }
+
+    /// Returns the text of the first heading of the slide, without its alignment flags.
+    pub fn heading(&self) -> Option<String> {
+        let root = markdown::to_mdast(&self.contents, &markdown::ParseOptions::gfm()).ok()?;
+        let heading = root
+            .children()?
+            .iter()
+            .find(|node| matches!(node, mdast::Node::Heading(_)))?;
+        let text = ALIGNMENT_FLAG
+            .replace_all(&heading.to_string(), "")
+            .trim()
+            .to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Returns the paths of the local images of the slide, relative to the project directory.
+    /// Remote images and data URLs are left out.
+    pub fn images(&self) -> Vec<String> {
+        fn collect(node: &mdast::Node, images: &mut Vec<String>) {
+            if let mdast::Node::Image(image) = node {
+                let remote = image.url.contains("://")
+                    || image.url.starts_with("//")
+                    || image.url.starts_with("data:");
+                if !image.url.is_empty() && !remote {
+                    images.push(image.url.clone());
+                }
+            }
+            for child in node.children().into_iter().flatten() {
+                collect(child, images);
+            }
+        }
+
+        let mut images = Vec::new();
+        if let Ok(root) = markdown::to_mdast(&self.contents, &markdown::ParseOptions::gfm()) {
+            collect(&root, &mut images);
+        }
+        images
+    }
+
+    /// Returns the title of the slide, as shown in the overview: its first heading, or the title set in the manifest.
+    pub fn display_title(&self) -> String {
+        self.heading()
+            .or_else(|| self.title.clone())
+            .unwrap_or_else(|| "Untitled".to_string())
+    }
+}
+
+/// Returns the lines of a text, each with whether it belongs to a fenced code block, fences included.
+/// A fence is closed by a marker made of the same character, at least as long as the one it was opened with.
+fn fenced_lines(text: &str) -> Vec<(&str, bool)> {
+    let mut fence: Option<&str> = None;
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            let marker = &trimmed[..trimmed.len() - trimmed.trim_start_matches(['`', '~']).len()];
+            let fenced = match fence {
+                Some(open) => {
+                    if marker.starts_with(open) && marker == trimmed {
+                        fence = None;
+                    }
+                    true
+                }
+                None if marker.len() >= 3
+                    && marker.chars().all(|c| c == marker.as_bytes()[0] as char) =>
+                {
+                    fence = Some(marker);
+                    true
+                }
+                None => false,
+            };
+            (line, fenced)
+        })
+        .collect()
+}
+
+/// Split the speaker notes out of the contents of a slide.
+/// Notes are written in a `<!-- notes: ... -->` comment, or in a block that starts with a
+/// `::: notes` line and ends with a `:::` line. Notes inside fenced code blocks are left alone.
+/// # Returns
+/// The contents without the notes, and the notes. Several notes are joined with a blank line.
+pub fn split_notes(text: &str) -> (String, String) {
+    let mut notes = Vec::new();
+    let mut contents = String::new();
+    // The text since the last fenced code block, the comments are taken out of it at the next one.
+    let mut prose = String::new();
+    let mut in_notes = false;
+    let mut block = String::new();
+
+    for (line, fenced) in fenced_lines(text) {
+        let trimmed = line.trim();
+
+        if in_notes {
+            if trimmed == ":::" {
+                in_notes = false;
+                notes.push(std::mem::take(&mut block));
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+            continue;
+        }
+
+        if fenced {
+            take_comment_notes(&mut prose, &mut contents, &mut notes);
+            contents.push_str(line);
+            contents.push('\n');
+        } else if trimmed.starts_with(":::") && trimmed[3..].trim() == "notes" {
+            // The notes are kept in the order they are written
+            take_comment_notes(&mut prose, &mut contents, &mut notes);
+            in_notes = true;
+        } else {
+            prose.push_str(line);
+            prose.push('\n');
+        }
+    }
+    // A block that is never closed runs to the end of the slide
+    if in_notes {
+        notes.push(block);
+    }
+    take_comment_notes(&mut prose, &mut contents, &mut notes);
+
+    let notes = notes
+        .iter()
+        .map(|note| note.trim())
+        .filter(|note| !note.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n\n");
+
+    (contents, notes)
+}
+
+/// Move the notes comments out of the text, into the notes, and the rest of the text to the contents.
+fn take_comment_notes(prose: &mut String, contents: &mut String, notes: &mut Vec<String>) {
+    let text = NOTES_COMMENT.replace_all(prose, |captures: &regex::Captures| {
+        notes.push(captures[1].to_string());
+        ""
+    });
+    contents.push_str(&text);
+    prose.clear();
+}
+
+/// Take the `<!-- incremental -->` directive out of the contents of a slide.
+/// The directive is a line of its own, outside of the fenced code blocks.
+/// # Returns
+/// The contents without the directive, and whether the slide had it.
+fn split_incremental(text: &str) -> (String, bool) {
+    let mut contents = String::new();
+    let mut incremental = false;
+    for (line, fenced) in fenced_lines(text) {
+        if !fenced && INCREMENTAL.is_match(line.trim()) {
+            incremental = true;
+            continue;
+        }
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    (contents, incremental)
+}
+
+/// Split the contents of a single-file deck into slides.
+/// A slide ends at a line containing only the separator. Separators inside fenced code blocks
+/// are ignored, and empty slides are dropped.
+pub fn split_slides(text: &str, separator: &str) -> Vec<String> {
+    let separator = separator.trim();
+    let mut slides = Vec::new();
+    let mut current = String::new();
+
+    for (line, fenced) in fenced_lines(text) {
+        if !fenced && line.trim() == separator {
+            slides.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    slides.push(current);
+
+    slides
+        .into_iter()
+        .filter(|slide| !slide.trim().is_empty())
+        .collect()
+}
+
+/// Read the numbered slides of a project directory, until the first missing slide.
+pub fn read_numbered(dir: &Path) -> Result<Vec<Slide>, Box<dyn Error>> {
+    let mut slides = Vec::new();
+    for i in 1u64.. {
+        let path = dir.join(format!("{}.md", i));
+        if !path.exists() {
+            break;
+        }
+        let contents = fs::read_to_string(&path)?;
+        slides.push(Slide::new(path, &contents, None));
+    }
+    Ok(slides)
+}
+
+/// Read a single-file deck and split it into slides.
+pub fn read_file(path: &Path, separator: &str) -> Result<Vec<Slide>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(split_slides(&text, separator)
+        .into_iter()
+        .map(|contents| Slide::new(path.to_path_buf(), &contents, None))
+        .collect())
+}
+
+/// A slide listed in the manifest.
+/// A slide is either a bare path, or a mapping with the path and the options of the slide.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestSlide {
+    file: PathBuf,
+    #[serde(default)]
+    title: Option<String>,
+    // Skipped slides stay in the manifest, but are not presented.
+    #[serde(default)]
+    skip: bool,
+    #[serde(default)]
+    incremental: bool,
+}
+
+/// The manifest of a deck.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    title: Option<String>,
+    slides: Vec<serde_yaml::Value>,
+}
+
+/// Returns true if the file is a deck manifest rather than a markdown deck.
+pub fn is_manifest(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("yml") | Some("yaml")
+    )
+}
+
+/// A deck with its optional title, as read from the project.
+pub struct Deck {
+    pub title: Option<String>,
+    pub slides: Vec<Slide>,
+}
+
+/// Returns the slide files listed in a manifest, relative to the directory of the manifest.
+/// Skipped slides are included, this is used to copy the slides of a template.
+pub fn manifest_files(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let (_, slides) = parse_manifest(path)?;
+    Ok(slides.into_iter().map(|slide| slide.file).collect())
+}
+
+/// Parse a manifest into its title and its slides.
+/// The slides are parsed one by one, so that an error points at the slide it was found in.
+fn parse_manifest(path: &Path) -> Result<(Option<String>, Vec<ManifestSlide>), Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let manifest: Manifest = serde_yaml::from_str(&text)
+        .map_err(|err| format!("Invalid manifest {}: {}", path.display(), err))?;
+
+    let mut slides = Vec::new();
+    for (i, entry) in manifest.slides.into_iter().enumerate() {
+        let slide = match entry {
+            serde_yaml::Value::String(file) => ManifestSlide {
+                file: PathBuf::from(file),
+                title: None,
+                skip: false,
+                incremental: false,
+            },
+            entry => serde_yaml::from_value(entry).map_err(|err| {
+                format!(
+                    "Invalid slide {} in manifest {}: {}",
+                    i + 1,
+                    path.display(),
+                    err
+                )
+            })?,
+        };
+        slides.push(slide);
+    }
+
+    Ok((manifest.title, slides))
+}
+
+/// Read the slides listed in a manifest.
+/// The slide files are resolved relative to the directory of the manifest, so one slide file can
+/// be shared by several decks.
+pub fn read_manifest(path: &Path) -> Result<Deck, Box<dyn Error>> {
+    let (title, manifest_slides) = parse_manifest(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut slides = Vec::new();
+    for slide in manifest_slides.into_iter().filter(|slide| !slide.skip) {
+        let path = dir.join(&slide.file);
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| format!("Could not read slide {}: {}", path.display(), err))?;
+        let mut deck_slide = Slide::new(path, &contents, slide.title);
+        deck_slide.incremental |= slide.incremental;
+        slides.push(deck_slide);
+    }
+
+    Ok(Deck { title, slides })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_slides_on_separator_lines() {
+        let slides = split_slides("# One\n---\n# Two\n\n---\n\n---\n# Three\n", "---");
+        assert_eq!(slides, vec!["# One\n", "# Two\n\n", "# Three\n"]);
+    }
+
+    #[test]
+    fn split_slides_ignores_separator_inside_fence() {
+        let text = "# One\n```yaml\n---\nkey: value\n```\n---\n# Two\n";
+        let slides = split_slides(text, "---");
+        assert_eq!(
+            slides,
+            vec!["# One\n```yaml\n---\nkey: value\n```\n", "# Two\n"]
+        );
+    }
+
+    #[test]
+    fn split_slides_fence_closed_by_longer_marker() {
+        let text = "~~~\n<!-- slide -->\n~~~~~\n<!-- slide -->\n# Two\n";
+        let slides = split_slides(text, "<!-- slide -->");
+        assert_eq!(slides, vec!["~~~\n<!-- slide -->\n~~~~~\n", "# Two\n"]);
+    }
+
+    #[test]
+    fn split_slides_fence_not_closed_by_other_marker() {
+        let text = "```\n~~~\n---\n```\n---\n# Two\n";
+        let slides = split_slides(text, "---");
+        assert_eq!(slides, vec!["```\n~~~\n---\n```\n", "# Two\n"]);
+    }
+
+    /// Write the files of a deck to a temporary directory, and returns the path of its manifest.
+    fn write_deck(dir: &tempfile::TempDir, manifest: &str, files: &[(&str, &str)]) -> PathBuf {
+        for (name, contents) in files {
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        let path = dir.path().join(MANIFEST_FILE);
+        fs::write(&path, manifest).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_manifest_with_title_skip_and_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = "title: Talk\nslides:\n  - intro.md\n  - file: skipped.md\n    skip: true\n  - file: list.md\n    title: List\n    incremental: true\n";
+        let path = write_deck(
+            &dir,
+            manifest,
+            &[
+                ("intro.md", "# Intro\n"),
+                ("skipped.md", "# Skipped\n"),
+                ("list.md", "- a\n- b\n"),
+            ],
+        );
+
+        let deck = read_manifest(&path).unwrap();
+        assert_eq!(deck.title.as_deref(), Some("Talk"));
+        assert_eq!(deck.slides.len(), 2);
+        assert_eq!(deck.slides[0].contents, "# Intro\n");
+        assert_eq!(deck.slides[0].title, None);
+        assert!(!deck.slides[0].incremental);
+        assert_eq!(deck.slides[1].title.as_deref(), Some("List"));
+        assert!(deck.slides[1].incremental);
+        assert_eq!(deck.slides[1].display_title(), "List");
+    }
+
+    #[test]
+    fn manifest_files_include_skipped_slides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_deck(
+            &dir,
+            "slides:\n  - a.md\n  - file: b.md\n    skip: true\n",
+            &[],
+        );
+        assert_eq!(
+            manifest_files(&path).unwrap(),
+            vec![PathBuf::from("a.md"), PathBuf::from("b.md")]
+        );
+    }
+
+    #[test]
+    fn read_manifest_with_missing_slide() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_deck(&dir, "slides:\n  - missing.md\n", &[]);
+        let err = read_manifest(&path).err().unwrap().to_string();
+        assert!(err.starts_with("Could not read slide"), "{}", err);
+        assert!(err.contains("missing.md"), "{}", err);
+    }
+
+    #[test]
+    fn read_manifest_with_unknown_slide_option() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_deck(
+            &dir,
+            "slides:\n  - a.md\n  - file: b.md\n    hidden: true\n",
+            &[],
+        );
+        let err = read_manifest(&path).err().unwrap().to_string();
+        assert!(err.starts_with("Invalid slide 2 in manifest"), "{}", err);
+    }
+
+    #[test]
+    fn read_missing_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_manifest(&dir.path().join(MANIFEST_FILE)).is_err());
+    }
+
+    #[test]
+    fn split_notes_from_comments_and_blocks() {
+        let text =
+            "# Title\n<!-- notes: Say hello. -->\nBody\n::: notes\nMention the **plan**.\n:::\n";
+        let (contents, notes) = split_notes(text);
+        assert_eq!(contents, "# Title\n\nBody\n");
+        assert_eq!(notes, "Say hello.\n\nMention the **plan**.");
+    }
+
+    #[test]
+    fn split_notes_block_runs_to_end_of_slide() {
+        let (contents, notes) = split_notes("Body\n::: notes\nUnclosed\n");
+        assert_eq!(contents, "Body\n");
+        assert_eq!(notes, "Unclosed");
+    }
+
+    #[test]
+    fn split_notes_leaves_fenced_code_alone() {
+        let text =
+            "```html\n<!-- notes: in code -->\n::: notes\n:::\n```\n<!-- notes: outside -->\n";
+        let (contents, notes) = split_notes(text);
+        assert_eq!(
+            contents,
+            "```html\n<!-- notes: in code -->\n::: notes\n:::\n```\n\n"
+        );
+        assert_eq!(notes, "outside");
+    }
+
+    #[test]
+    fn images_of_a_slide_are_local_only() {
+        let slide = Slide::new(
+            PathBuf::from("1.md"),
+            "# ![logo](logo.png)\n\n- ![a](img/a.png)\n- ![b](https://example.com/b.png)\n\n![c](data:image/png;base64,AA==)",
+            None,
+        );
+        assert_eq!(slide.images(), vec!["logo.png", "img/a.png"]);
+    }
+
+    #[test]
+    fn incremental_directive_outside_fences() {
+        let slide = Slide::new(
+            PathBuf::from("1.md"),
+            "# List\n<!-- incremental -->\n- a\n",
+            None,
+        );
+        assert!(slide.incremental);
+        assert_eq!(slide.contents, "# List\n- a\n");
+
+        let text = "```html\n<!-- incremental -->\n```\n";
+        let slide = Slide::new(PathBuf::from("1.md"), text, None);
+        assert!(!slide.incremental);
+        assert_eq!(slide.contents, text);
+    }
+}
@@ -0,0 +1,534 @@
+/// The graphics module draws the images of a slide in the terminal.
+/// Images are drawn with the Kitty graphics protocol, the iTerm2 inline-image protocol or sixel,
+/// depending on what the terminal supports. Other terminals get the image drawn with Unicode half blocks.
+///
+/// Graphics protocols draw at the cursor, so an image cannot be part of the text of the slide. Instead,
+/// the rows of the image are reserved with blank lines that carry an invisible marker. The markers are
+/// laid out along with the rest of the slide, and the image is drawn where its markers end up.
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::utils::strip_ansi_codes;
+
+/// The SGR parameter that starts a marker, `ESC [ 7319 ; <image> ; <row> m`.
+/// Markers look like color codes, so the layout treats them as zero-width.
+const MARKER: u32 = 7319;
+
+/// The size of a terminal cell in pixels, when the terminal does not report it.
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+lazy_static! {
+    /// The images of the slide that is being rendered, in the order they appear
+    static ref IMAGES: Mutex<Vec<Image>> = Mutex::new(Vec::new());
+
+    /// The encoded images, reused while the same slide is rendered again
+    static ref CACHE: Mutex<HashMap<CacheKey, Image>> = Mutex::new(HashMap::new());
+
+    /// The protocol that is used for the current slide, and the one used before it
+    static ref PROTOCOL: Mutex<(Protocol, Option<Protocol>)> = Mutex::new((Protocol::Blocks, None));
+
+    /// Matches the markers of the image rows
+    static ref MARKER_RE: Regex = Regex::new(&format!(r"\x1b\[{};(\d+);(\d+)m", MARKER)).unwrap();
+}
+
+/// How images are drawn, as set by `image_protocol` in style.yml.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Detected from the environment of the terminal.
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    /// Unicode half blocks, which work in any terminal with true colors.
+    Blocks,
+}
+
+impl Protocol {
+    /// Returns the protocol supported by the terminal.
+    /// The terminal is recognized from its environment variables. Inside tmux or screen, which do
+    /// not pass the graphics protocols through, the half blocks are used.
+    pub fn detect() -> Protocol {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if std::env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            Protocol::Blocks
+        } else if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "ghostty"
+        {
+            Protocol::Kitty
+        } else if program == "iTerm.app"
+            || program == "WezTerm"
+            || program == "mintty"
+            || var("LC_TERMINAL") == "iTerm2"
+        {
+            Protocol::Iterm
+        } else if term.contains("sixel") || term == "foot" || term.starts_with("mlterm") {
+            Protocol::Sixel
+        } else {
+            Protocol::Blocks
+        }
+    }
+
+    /// Resolves `Auto` to the protocol of the terminal.
+    fn resolve(self) -> Protocol {
+        match self {
+            Protocol::Auto => Protocol::detect(),
+            protocol => protocol,
+        }
+    }
+}
+
+/// An image, encoded for the terminal.
+#[derive(Clone)]
+struct Image {
+    modified: Option<SystemTime>,
+    cols: u32,
+    rows: u32,
+    // The escape sequence that draws the image, or its lines of half blocks.
+    data: String,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: PathBuf,
+    protocol: Protocol,
+    max_cols: u32,
+    max_rows: u32,
+}
+
+/// Forget the images of the previous slide, before a slide is rendered.
+/// # Arguments
+/// * `protocol` - The `image_protocol` of the style.
+pub fn reset(protocol: Protocol) {
+    IMAGES.lock().unwrap().clear();
+    let mut current = PROTOCOL.lock().unwrap();
+    let previous = current.0;
+    *current = (protocol.resolve(), Some(previous));
+}
+
+/// Returns the text that takes the place of an image in the slide.
+/// With a graphics protocol, these are blank lines with markers, which are replaced by the image
+/// when the slide is printed. With half blocks, these are the lines of the image itself.
+/// # Arguments
+/// * `path` - The path of the image file.
+/// * `max_cols` - The number of columns the image may take.
+/// * `max_rows` - The number of rows the image may take.
+/// # Returns
+/// The lines of the image, or an error if it could not be read.
+pub fn placeholder(path: &Path, max_cols: u32, max_rows: u32) -> Result<String, Box<dyn Error>> {
+    let protocol = PROTOCOL.lock().unwrap().0;
+    let image = load(path, protocol, max_cols.max(1), max_rows.max(1))?;
+
+    if protocol == Protocol::Blocks {
+        return Ok(image.data);
+    }
+
+    let mut images = IMAGES.lock().unwrap();
+    let index = images.len();
+    let lines = (0..image.rows)
+        .map(|row| {
+            format!(
+                "\x1b[{};{};{}m{}",
+                MARKER,
+                index,
+                row,
+                " ".repeat(image.cols as usize)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    images.push(image);
+
+    Ok(lines)
+}
+
+/// Reads and encodes an image, or returns it from the cache if the file did not change.
+fn load(
+    path: &Path,
+    protocol: Protocol,
+    max_cols: u32,
+    max_rows: u32,
+) -> Result<Image, Box<dyn Error>> {
+    let modified = std::fs::metadata(path)?.modified().ok();
+    let key = CacheKey {
+        path: path.to_path_buf(),
+        protocol,
+        max_cols,
+        max_rows,
+    };
+    if let Some(image) = CACHE.lock().unwrap().get(&key) {
+        if image.modified == modified {
+            return Ok(image.clone());
+        }
+    }
+
+    let decoded = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()?;
+    // A half block cell shows two pixels, one above the other
+    let (cell_width, cell_height) = match protocol {
+        Protocol::Blocks => (1, 2),
+        _ => cell_size(),
+    };
+
+    // The image is scaled down to fit, but never scaled up
+    let (width, height) = decoded.dimensions();
+    let scale = f64::min(
+        1.0,
+        f64::min(
+            (max_cols * cell_width) as f64 / width as f64,
+            (max_rows * cell_height) as f64 / height as f64,
+        ),
+    );
+    let width = ((width as f64 * scale) as u32).max(1);
+    let height = ((height as f64 * scale) as u32).max(1);
+    let cols = width.div_ceil(cell_width).min(max_cols);
+    let rows = height.div_ceil(cell_height).min(max_rows);
+
+    let data = match protocol {
+        Protocol::Kitty => kitty(
+            &decoded.resize_exact(width, height, FilterType::Triangle),
+            cols,
+            rows,
+        )?,
+        Protocol::Iterm => iterm(
+            &decoded.resize_exact(width, height, FilterType::Triangle),
+            cols,
+            rows,
+        )?,
+        Protocol::Sixel => sixel(
+            &decoded
+                .resize_exact(width, height, FilterType::Triangle)
+                .to_rgba8(),
+        ),
+        Protocol::Blocks | Protocol::Auto => blocks(
+            &decoded
+                .resize_exact(cols, rows * 2, FilterType::Triangle)
+                .to_rgba8(),
+        ),
+    };
+
+    let image = Image {
+        modified,
+        cols,
+        rows,
+        data,
+    };
+    CACHE.lock().unwrap().insert(key, image.clone());
+    Ok(image)
+}
+
+/// Returns the size of a terminal cell in pixels.
+fn cell_size() -> (u32, u32) {
+    match (termion::terminal_size(), termion::terminal_size_pixels()) {
+        (Ok((cols, rows)), Ok((width, height)))
+            if cols > 0 && rows > 0 && width > 0 && height > 0 =>
+        {
+            ((width / cols).max(1) as u32, (height / rows).max(1) as u32)
+        }
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+fn png(image: &DynamicImage) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes.into_inner()))
+}
+
+/// Encodes an image for the Kitty graphics protocol.
+/// The PNG data is sent in chunks of 4096 bytes, and the image is scaled to the given cells.
+fn kitty(image: &DynamicImage, cols: u32, rows: u32) -> Result<String, Box<dyn Error>> {
+    let data = png(image)?;
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    let mut result = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        let chunk = std::str::from_utf8(chunk)?;
+        if i == 0 {
+            result.push_str(&format!(
+                "\x1b_Ga=T,f=100,C=1,q=2,c={},r={},m={};{}\x1b\\",
+                cols, rows, more, chunk
+            ));
+        } else {
+            result.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    Ok(result)
+}
+
+/// Encodes an image for the iTerm2 inline-image protocol.
+fn iterm(image: &DynamicImage, cols: u32, rows: u32) -> Result<String, Box<dyn Error>> {
+    Ok(format!(
+        "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=1:{}\x07",
+        cols,
+        rows,
+        png(image)?
+    ))
+}
+
+/// Encodes an image as sixel.
+/// The colors are reduced to a 6x6x6 color cube, and transparent pixels are left out.
+fn sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let level = |value: u8| (value as u32 * 5 + 127) / 255;
+    let color = |pixel: &Rgba<u8>| -> Option<u32> {
+        if pixel[3] < 128 {
+            return None;
+        }
+        Some(level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]))
+    };
+
+    let mut result = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for index in 0..216 {
+        let percent = |level: u32| level * 100 / 5;
+        result.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / 36),
+            percent(index / 6 % 6),
+            percent(index % 6)
+        ));
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_height = (height - band).min(6);
+        let mut colors: Vec<u32> = (0..width)
+            .flat_map(|x| (0..band_height).filter_map(move |y| color(image.get_pixel(x, band + y))))
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for color_index in colors {
+            result.push_str(&format!("#{}", color_index));
+            let sixels = (0..width).map(|x| {
+                let bits = (0..band_height)
+                    .filter(|y| color(image.get_pixel(x, band + y)) == Some(color_index))
+                    .fold(0, |bits, y| bits | 1 << y);
+                (63 + bits) as u8 as char
+            });
+            push_run_length(&mut result, sixels);
+            result.push('$');
+        }
+        result.push('-');
+    }
+
+    result.push_str("\x1b\\");
+    result
+}
+
+/// Pushes the sixels, with runs of the same sixel compressed.
+fn push_run_length(result: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |result: &mut String, run: Option<(char, usize)>| match run {
+        Some((sixel, count)) if count > 3 => result.push_str(&format!("!{}{}", count, sixel)),
+        Some((sixel, count)) => result.push_str(&sixel.to_string().repeat(count)),
+        None => {}
+    };
+    for sixel in sixels {
+        run = match run {
+            Some((current, count)) if current == sixel => Some((current, count + 1)),
+            _ => {
+                flush(result, run);
+                Some((sixel, 1))
+            }
+        };
+    }
+    flush(result, run);
+}
+
+/// Draws an image with half blocks, two pixels to a cell.
+/// The upper pixel is the foreground of `▀` and the lower one its background.
+fn blocks(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut lines = Vec::new();
+    for y in (0..height).step_by(2) {
+        let mut line = String::new();
+        for x in 0..width {
+            let upper = image.get_pixel(x, y);
+            let lower = if y + 1 < height {
+                *image.get_pixel(x, y + 1)
+            } else {
+                Rgba([0, 0, 0, 0])
+            };
+            let cell = match (upper[3] >= 128, lower[3] >= 128) {
+                (true, true) => format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+                    upper[0], upper[1], upper[2], lower[0], lower[1], lower[2]
+                ),
+                (true, false) => format!("\x1b[38;2;{};{};{}m▀", upper[0], upper[1], upper[2]),
+                (false, true) => format!("\x1b[38;2;{};{};{}m▄", lower[0], lower[1], lower[2]),
+                (false, false) => " ".to_string(),
+            };
+            line.push_str(&cell);
+            line.push_str("\x1b[0m");
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Prints a slide, and draws its images where their markers are.
+/// The markers are removed from the text, and images that do not fit in the visible rows are left out.
+/// # Arguments
+/// * `out` - The terminal to print to, with the cursor at the top left corner.
+/// * `slide` - The text of the slide, as it is printed.
+/// * `height` - The number of rows the slide may take.
+pub fn print_slide<W: Write>(out: &mut W, slide: &str, height: u16) -> io::Result<()> {
+    let (protocol, previous) = *PROTOCOL.lock().unwrap();
+
+    // Kitty keeps the images of the previous slide until they are deleted
+    if protocol == Protocol::Kitty || previous == Some(Protocol::Kitty) {
+        write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+    }
+
+    // The position of every image is the position of its first row, found from any of its rows
+    let mut positions: HashMap<usize, (i64, usize)> = HashMap::new();
+    let mut text = String::new();
+    for (line_index, line) in slide.split('\n').enumerate() {
+        for captures in MARKER_RE.captures_iter(line) {
+            let marker = captures.get(0).unwrap();
+            let index: usize = captures[1].parse().unwrap_or(0);
+            let row: i64 = captures[2].parse().unwrap_or(0);
+            let col = UnicodeWidthStr::width(strip_ansi_codes(&line[..marker.start()]).as_str());
+            positions
+                .entry(index)
+                .or_insert((line_index as i64 - row, col));
+        }
+        if line_index > 0 {
+            text.push('\n');
+        }
+        text.push_str(&MARKER_RE.replace_all(line, ""));
+    }
+
+    write!(out, "{}", text)?;
+    if positions.is_empty() {
+        return out.flush();
+    }
+
+    // A slide taller than the terminal scrolls it
+    let lines = text.split('\n').count() as i64;
+    let (_, terminal_height) = termion::terminal_size()?;
+    let scrolled = (lines - terminal_height as i64).max(0);
+
+    write!(out, "{}", termion::cursor::Save)?;
+    let images = IMAGES.lock().unwrap();
+    for (index, (line, col)) in positions {
+        let image = match images.get(index) {
+            Some(image) => image,
+            None => continue,
+        };
+        let top = line - scrolled;
+        // The image is only drawn when all of its rows are printed and visible
+        if top < 0 || line + image.rows as i64 > lines || top + image.rows as i64 > height as i64 {
+            continue;
+        }
+        write!(
+            out,
+            "{}{}",
+            termion::cursor::Goto(col as u16 + 1, top as u16 + 1),
+            image.data
+        )?;
+    }
+    write!(out, "{}", termion::cursor::Restore)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::visible_width;
+
+    lazy_static! {
+        /// The protocol and the images of the slide are shared, the tests that render images take turns
+        static ref RENDERING: Mutex<()> = Mutex::new(());
+    }
+
+    /// Writes a red image of the given size, and returns its path.
+    fn red_image(dir: &tempfile::TempDir, width: u32, height: u32) -> PathBuf {
+        let path = dir.path().join(format!("{}x{}.png", width, height));
+        RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn placeholder_with_blocks_is_the_image() {
+        let _rendering = RENDERING.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        reset(Protocol::Blocks);
+
+        // Two pixels to a cell, scaled down to the columns it may take.
+        let lines = placeholder(&red_image(&dir, 8, 8), 4, 10).unwrap();
+        let lines: Vec<&str> = lines.split('\n').collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| visible_width(line) == 4));
+        assert!(lines[0].contains("\x1b[38;2;255;0;0;48;2;255;0;0m▀"));
+
+        // Never scaled up.
+        let lines = placeholder(&red_image(&dir, 2, 2), 40, 40).unwrap();
+        assert_eq!(lines.split('\n').count(), 1);
+        assert_eq!(visible_width(&lines), 2);
+    }
+
+    #[test]
+    fn placeholder_with_a_protocol_is_marked_rows() {
+        let _rendering = RENDERING.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        reset(Protocol::Kitty);
+
+        let first = placeholder(&red_image(&dir, 16, 32), 10, 10).unwrap();
+        let second = placeholder(&red_image(&dir, 16, 16), 10, 10).unwrap();
+        let (cols, rows) = cell_size();
+        let expected = |index: usize, cols: u32, rows: u32| {
+            (0..rows)
+                .map(|row| {
+                    format!(
+                        "\x1b[{};{};{}m{}",
+                        MARKER,
+                        index,
+                        row,
+                        " ".repeat(cols as usize)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        assert_eq!(
+            first,
+            expected(0, 16_u32.div_ceil(cols), 32_u32.div_ceil(rows))
+        );
+        assert_eq!(
+            second,
+            expected(1, 16_u32.div_ceil(cols), 16_u32.div_ceil(rows))
+        );
+        assert_eq!(strip_ansi_codes(&first).trim(), "");
+        assert_eq!(IMAGES.lock().unwrap().len(), 2);
+        reset(Protocol::Blocks);
+    }
+
+    #[test]
+    fn placeholder_of_a_missing_image_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(placeholder(&dir.path().join("missing.png"), 10, 10).is_err());
+    }
+}
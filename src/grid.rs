@@ -0,0 +1,624 @@
+/// The grid turns a slide, as it is printed in the terminal, into a grid of cells with their
+/// characters and colors. The exports draw the slides from their grids, so that a slide looks the
+/// same in a document as in the terminal.
+use unicode_width::UnicodeWidthChar;
+
+/// The colors of the text and of the background where the slide uses those of the terminal, as in the HTML deck.
+pub const DEFAULT_FOREGROUND: Rgb = Rgb(0xe5, 0xe5, 0xe5);
+pub const DEFAULT_BACKGROUND: Rgb = Rgb(0x1e, 0x1e, 0x1e);
+
+/// The combining character the terminal renderer strikes text through with.
+const STRIKETHROUGH: char = '\u{0336}';
+
+/// A color, by its red, green and blue components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Returns the color `amount` of the way from this color to the other one.
+    pub fn mix(self, other: Rgb, amount: f32) -> Rgb {
+        let mix =
+            |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+/// Returns a color of the 256-color palette of the terminal.
+/// The first 16 colors are those of the xterm palette, as in the HTML deck.
+fn palette(index: u8) -> Rgb {
+    const BASIC: [Rgb; 16] = [
+        Rgb(0x00, 0x00, 0x00),
+        Rgb(0xcd, 0x00, 0x00),
+        Rgb(0x00, 0xcd, 0x00),
+        Rgb(0xcd, 0xcd, 0x00),
+        Rgb(0x00, 0x00, 0xee),
+        Rgb(0xcd, 0x00, 0xcd),
+        Rgb(0x00, 0xcd, 0xcd),
+        Rgb(0xe5, 0xe5, 0xe5),
+        Rgb(0x7f, 0x7f, 0x7f),
+        Rgb(0xff, 0x00, 0x00),
+        Rgb(0x00, 0xff, 0x00),
+        Rgb(0xff, 0xff, 0x00),
+        Rgb(0x5c, 0x5c, 0xff),
+        Rgb(0xff, 0x00, 0xff),
+        Rgb(0x00, 0xff, 0xff),
+        Rgb(0xff, 0xff, 0xff),
+    ];
+    match index {
+        0..=15 => BASIC[index as usize],
+        // A 6x6x6 color cube
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            Rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        // 24 shades of gray
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// The look of a cell, as set by the color codes printed before it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub foreground: Option<Rgb>,
+    pub background: Option<Rgb>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub reverse: bool,
+}
+
+impl Attributes {
+    /// Returns the colors a cell is drawn with, its text and its background, once reversed and dimmed.
+    pub fn colors(&self) -> (Rgb, Rgb) {
+        let mut foreground = self.foreground.unwrap_or(DEFAULT_FOREGROUND);
+        let mut background = self.background.unwrap_or(DEFAULT_BACKGROUND);
+        if self.reverse {
+            std::mem::swap(&mut foreground, &mut background);
+        }
+        if self.dim {
+            foreground = foreground.mix(background, 0.5);
+        }
+        (foreground, background)
+    }
+
+    /// Apply the parameters of a color code, such as `1;38;2;255;0;0`.
+    fn apply(&mut self, parameters: &str) {
+        let parameters: Vec<u16> = parameters
+            .split(';')
+            .map(|parameter| parameter.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < parameters.len() {
+            match parameters[i] {
+                0 => *self = Attributes::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.reverse = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.reverse = false,
+                29 => self.strikethrough = false,
+                code @ 30..=37 => self.foreground = Some(palette((code - 30) as u8)),
+                code @ 90..=97 => self.foreground = Some(palette((code - 90 + 8) as u8)),
+                39 => self.foreground = None,
+                code @ 40..=47 => self.background = Some(palette((code - 40) as u8)),
+                code @ 100..=107 => self.background = Some(palette((code - 100 + 8) as u8)),
+                49 => self.background = None,
+                code @ (38 | 48) => {
+                    // 38;5;<index> and 38;2;<r>;<g>;<b>, and the same for the background
+                    let color = match parameters.get(i + 1) {
+                        Some(5) => {
+                            let color = parameters.get(i + 2).map(|index| palette(*index as u8));
+                            i += 2;
+                            color
+                        }
+                        Some(2) => {
+                            let component = |offset: usize| {
+                                parameters.get(i + offset).copied().unwrap_or(0) as u8
+                            };
+                            let color = Rgb(component(2), component(3), component(4));
+                            i += 4;
+                            Some(color)
+                        }
+                        _ => None,
+                    };
+                    if code == 38 {
+                        self.foreground = color;
+                    } else {
+                        self.background = color;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// A cell of the grid. The second cell of a wide character holds no character of its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub ch: Option<char>,
+    pub attributes: Attributes,
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell {
+            ch: Some(' '),
+            attributes: Attributes::default(),
+        }
+    }
+}
+
+/// The cells of a slide, row by row.
+pub struct Grid {
+    pub cols: usize,
+    pub rows: usize,
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Grid {
+    /// Lay out a slide, as printed in the terminal, in a grid.
+    /// The colors carry over from one line to the next, as they do in the terminal.
+    /// # Arguments
+    /// * `text` - The slide, with its color codes.
+    /// * `cols` - The number of columns of the grid. Wider lines widen the grid.
+    /// * `rows` - The number of rows of the grid. Taller slides add rows, so that nothing is cut.
+    /// # Returns
+    /// The grid of the slide.
+    pub fn parse(text: &str, cols: usize, rows: usize) -> Grid {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        let mut attributes = Attributes::default();
+        let mut cells: Vec<Vec<Cell>> = Vec::new();
+
+        for line in text.split('\n') {
+            let mut row: Vec<Cell> = Vec::new();
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    // A control sequence: color codes are applied, the others take no room.
+                    '\x1b' => {
+                        if chars.peek() != Some(&'[') {
+                            continue;
+                        }
+                        chars.next();
+                        let mut parameters = String::new();
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                if c == 'm' {
+                                    attributes.apply(&parameters);
+                                }
+                                break;
+                            }
+                            parameters.push(c);
+                        }
+                    }
+                    '\t' => row.push(Cell {
+                        ch: Some(' '),
+                        attributes,
+                    }),
+                    STRIKETHROUGH => {
+                        if let Some(cell) = row.iter_mut().rev().find(|cell| cell.ch.is_some()) {
+                            cell.attributes.strikethrough = true;
+                        }
+                    }
+                    c => match UnicodeWidthChar::width(c) {
+                        Some(0) | None => {}
+                        Some(width) => {
+                            row.push(Cell {
+                                ch: Some(c),
+                                attributes,
+                            });
+                            for _ in 1..width {
+                                row.push(Cell {
+                                    ch: None,
+                                    attributes,
+                                });
+                            }
+                        }
+                    },
+                }
+            }
+            cells.push(row);
+        }
+
+        let cols = cells
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0)
+            .max(cols);
+        let rows = cells.len().max(rows);
+        cells.resize(rows, Vec::new());
+        for row in cells.iter_mut() {
+            row.resize(cols, Cell::default());
+        }
+        Grid { cols, rows, cells }
+    }
+
+    /// Draw the grid on a canvas: the backgrounds of the cells first, then their text and shapes.
+    /// The background of the canvas is left to it, only the cells with a background of their own are filled.
+    /// # Arguments
+    /// * `canvas` - The canvas to draw on.
+    /// * `cell_width` - The width of a cell, in the units of the canvas.
+    /// * `cell_height` - The height of a cell, in the units of the canvas.
+    pub fn draw(&self, canvas: &mut impl Canvas, cell_width: f32, cell_height: f32) {
+        let size = CellSize {
+            width: cell_width,
+            height: cell_height,
+        };
+        for (row, cells) in self.cells.iter().enumerate() {
+            let y = row as f32 * cell_height;
+
+            // The backgrounds, in runs of the same color
+            let mut col = 0;
+            while col < cells.len() {
+                let (_, background) = cells[col].attributes.colors();
+                let start = col;
+                while col < cells.len() && cells[col].attributes.colors().1 == background {
+                    col += 1;
+                }
+                if background != DEFAULT_BACKGROUND {
+                    canvas.fill(
+                        background,
+                        start as f32 * cell_width,
+                        y,
+                        (col - start) as f32 * cell_width,
+                        cell_height,
+                    );
+                }
+            }
+
+            // The text, in runs of the same look, and the shapes in between
+            let mut col = 0;
+            while col < cells.len() {
+                let attributes = cells[col].attributes;
+                let x = col as f32 * cell_width;
+                if let Some(cell_shape) = cells[col].ch.and_then(shape) {
+                    // A horizontal line across several cells, such as a border, is drawn at once.
+                    if let Some(weight) = horizontal(cell_shape) {
+                        let start = col;
+                        while col < cells.len()
+                            && cells[col].attributes == attributes
+                            && cells[col].ch.and_then(shape).and_then(horizontal) == Some(weight)
+                        {
+                            col += 1;
+                        }
+                        let (foreground, _) = attributes.colors();
+                        let width = size.line(weight);
+                        canvas.fill(
+                            foreground,
+                            x,
+                            y + (cell_height - width) / 2.0,
+                            (col - start) as f32 * cell_width,
+                            width,
+                        );
+                        continue;
+                    }
+                    draw_shape(canvas, cell_shape, &attributes, x, y, &size);
+                    col += 1;
+                    continue;
+                }
+
+                // A wide character is a run of its own, so that the text of a run fills its columns
+                // with one character per column.
+                let wide = |col: usize| cells.get(col + 1).is_some_and(|cell| cell.ch.is_none());
+                let start = col;
+                if wide(col) {
+                    col += 2;
+                } else {
+                    while col < cells.len()
+                        && cells[col].attributes == attributes
+                        && cells[col].ch.and_then(shape).is_none()
+                        && !wide(col)
+                    {
+                        col += 1;
+                    }
+                }
+                draw_text(canvas, &cells[start..col], &attributes, x, y, &size);
+            }
+        }
+    }
+}
+
+/// A document a grid is drawn on, such as a page of a PDF document or an SVG image.
+/// The positions are those of the top left corners, from the top left corner of the grid.
+pub trait Canvas {
+    /// Fill a rectangle with a color.
+    fn fill(&mut self, color: Rgb, x: f32, y: f32, width: f32, height: f32);
+
+    /// Write a run of text across the cells of a row, with the font and the color of its attributes.
+    /// The text has one character per cell, or a wide character over two cells.
+    fn text(&mut self, text: &str, attributes: &Attributes, x: f32, y: f32, width: f32);
+
+    /// Draw a checkbox, the outline of a square, with a check mark when it is checked.
+    fn checkbox(&mut self, color: Rgb, x: f32, y: f32, size: f32, checked: bool);
+}
+
+/// The size of a cell, in the units of a canvas.
+struct CellSize {
+    width: f32,
+    height: f32,
+}
+
+impl CellSize {
+    /// Returns the width of a line of a box-drawing character.
+    fn line(&self, line: Line) -> f32 {
+        match line {
+            Line::None => 0.0,
+            Line::Light => self.width * 0.1,
+            Line::Heavy => self.width * 0.24,
+        }
+    }
+}
+
+/// Draw a run of text, with its underline and strikethrough.
+/// The spaces around the text are not written, the backgrounds are already filled.
+fn draw_text(
+    canvas: &mut impl Canvas,
+    cells: &[Cell],
+    attributes: &Attributes,
+    x: f32,
+    y: f32,
+    size: &CellSize,
+) {
+    let (foreground, _) = attributes.colors();
+    let width = cells.len() as f32 * size.width;
+    let thickness = size.line(Line::Light);
+    if attributes.underline {
+        canvas.fill(foreground, x, y + size.height * 0.875, width, thickness);
+    }
+    if attributes.strikethrough {
+        canvas.fill(foreground, x, y + size.height * 0.55, width, thickness);
+    }
+
+    let written = |cell: &Cell| cell.ch.is_some_and(|c| c != ' ');
+    let (first, last) = match (
+        cells.iter().position(written),
+        cells.iter().rposition(written),
+    ) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+    // The text ends with the second cell of its last character, when it is wide.
+    let mut end = last + 1;
+    while end < cells.len() && cells[end].ch.is_none() {
+        end += 1;
+    }
+    let text: String = cells[first..end]
+        .iter()
+        .filter_map(|cell| cell.ch)
+        .collect();
+    canvas.text(
+        &text,
+        attributes,
+        x + first as f32 * size.width,
+        y,
+        (end - first) as f32 * size.width,
+    );
+}
+
+/// Draw a character that the fonts of the documents do not have, in its cell.
+fn draw_shape(
+    canvas: &mut impl Canvas,
+    shape: Shape,
+    attributes: &Attributes,
+    x: f32,
+    y: f32,
+    size: &CellSize,
+) {
+    let (foreground, background) = attributes.colors();
+    let (center_x, center_y) = (x + size.width / 2.0, y + size.height / 2.0);
+    match shape {
+        Shape::Lines {
+            left,
+            right,
+            up,
+            down,
+        } => {
+            // Every line runs from the center of the cell to an edge, and overlaps the center by half
+            // its width, so that the corners are closed. A line straight through the cell is drawn at once.
+            let (width, half) = (size.line(left), size.line(left) / 2.0);
+            if left == right && width > 0.0 {
+                canvas.fill(foreground, x, center_y - half, size.width, width);
+            } else {
+                if width > 0.0 {
+                    canvas.fill(
+                        foreground,
+                        x,
+                        center_y - half,
+                        size.width / 2.0 + half,
+                        width,
+                    );
+                }
+                let (width, half) = (size.line(right), size.line(right) / 2.0);
+                if width > 0.0 {
+                    canvas.fill(
+                        foreground,
+                        center_x - half,
+                        center_y - half,
+                        size.width / 2.0 + half,
+                        width,
+                    );
+                }
+            }
+            let (width, half) = (size.line(up), size.line(up) / 2.0);
+            if up == down && width > 0.0 {
+                canvas.fill(foreground, center_x - half, y, width, size.height);
+            } else {
+                if width > 0.0 {
+                    canvas.fill(
+                        foreground,
+                        center_x - half,
+                        y,
+                        width,
+                        size.height / 2.0 + half,
+                    );
+                }
+                let (width, half) = (size.line(down), size.line(down) / 2.0);
+                if width > 0.0 {
+                    canvas.fill(
+                        foreground,
+                        center_x - half,
+                        center_y - half,
+                        width,
+                        size.height / 2.0 + half,
+                    );
+                }
+            }
+        }
+        Shape::Block {
+            left,
+            top,
+            right,
+            bottom,
+            shade,
+        } => canvas.fill(
+            background.mix(foreground, shade),
+            x + left * size.width,
+            y + top * size.height,
+            (right - left) * size.width,
+            (bottom - top) * size.height,
+        ),
+        Shape::Checkbox(checked) => {
+            let side = size.width * 5.0 / 6.0;
+            canvas.checkbox(
+                foreground,
+                center_x - side / 2.0,
+                center_y - side / 2.0,
+                side,
+                checked,
+            );
+        }
+    }
+}
+
+/// Returns the weight of a box-drawing character that is a horizontal line from edge to edge, if it is one.
+fn horizontal(shape: Shape) -> Option<Line> {
+    match shape {
+        Shape::Lines {
+            left,
+            right,
+            up: Line::None,
+            down: Line::None,
+        } if left == right && left != Line::None => Some(left),
+        _ => None,
+    }
+}
+
+/// The weight of a line of a box-drawing character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Line {
+    None,
+    Light,
+    Heavy,
+}
+
+/// A character that is drawn as a shape, because the fonts of the documents do not have it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    /// A box-drawing character, by its lines from the center of the cell to its left, right, top and bottom edges.
+    Lines {
+        left: Line,
+        right: Line,
+        up: Line,
+        down: Line,
+    },
+    /// A block element: the part of the cell it fills, from its left, top, right and bottom edges
+    /// as fractions of the cell, and how much of the color of the text it is filled with.
+    Block {
+        left: f32,
+        top: f32,
+        right: f32,
+        bottom: f32,
+        shade: f32,
+    },
+    /// A task list checkbox, checked or not.
+    Checkbox(bool),
+}
+
+/// Returns the shape a character is drawn as, or None if it is drawn with the font.
+/// Double box-drawing lines are drawn as heavy lines, and dashed lines as full lines.
+pub fn shape(c: char) -> Option<Shape> {
+    let block = |left, top, right, bottom, shade| {
+        Some(Shape::Block {
+            left,
+            top,
+            right,
+            bottom,
+            shade,
+        })
+    };
+    let (arms, weight) = match c {
+        '█' => return block(0.0, 0.0, 1.0, 1.0, 1.0),
+        '▀' => return block(0.0, 0.0, 1.0, 0.5, 1.0),
+        '▄' => return block(0.0, 0.5, 1.0, 1.0, 1.0),
+        '▌' => return block(0.0, 0.0, 0.5, 1.0, 1.0),
+        '▐' => return block(0.5, 0.0, 1.0, 1.0, 1.0),
+        '░' => return block(0.0, 0.0, 1.0, 1.0, 0.25),
+        '▒' => return block(0.0, 0.0, 1.0, 1.0, 0.5),
+        '▓' => return block(0.0, 0.0, 1.0, 1.0, 0.75),
+        '☐' => return Some(Shape::Checkbox(false)),
+        '☑' | '☒' => return Some(Shape::Checkbox(true)),
+
+        '─' | '┄' | '┈' | '╌' => ("lr", Line::Light),
+        '│' | '┆' | '┊' | '╎' => ("ud", Line::Light),
+        '┌' | '╭' => ("rd", Line::Light),
+        '┐' | '╮' => ("ld", Line::Light),
+        '└' | '╰' => ("ru", Line::Light),
+        '┘' | '╯' => ("lu", Line::Light),
+        '├' => ("udr", Line::Light),
+        '┤' => ("udl", Line::Light),
+        '┬' => ("lrd", Line::Light),
+        '┴' => ("lru", Line::Light),
+        '┼' => ("lrud", Line::Light),
+        '╴' => ("l", Line::Light),
+        '╵' => ("u", Line::Light),
+        '╶' => ("r", Line::Light),
+        '╷' => ("d", Line::Light),
+
+        '━' | '┅' | '┉' | '╍' | '═' => ("lr", Line::Heavy),
+        '┃' | '┇' | '┋' | '╏' | '║' => ("ud", Line::Heavy),
+        '┏' | '╔' => ("rd", Line::Heavy),
+        '┓' | '╗' => ("ld", Line::Heavy),
+        '┗' | '╚' => ("ru", Line::Heavy),
+        '┛' | '╝' => ("lu", Line::Heavy),
+        '┣' | '╠' => ("udr", Line::Heavy),
+        '┫' | '╣' => ("udl", Line::Heavy),
+        '┳' | '╦' => ("lrd", Line::Heavy),
+        '┻' | '╩' => ("lru", Line::Heavy),
+        '╋' | '╬' => ("lrud", Line::Heavy),
+        _ => return None,
+    };
+    let arm = |name| {
+        if arms.contains(name) {
+            weight
+        } else {
+            Line::None
+        }
+    };
+    Some(Shape::Lines {
+        left: arm('l'),
+        right: arm('r'),
+        up: arm('u'),
+        down: arm('d'),
+    })
+}
@@ -0,0 +1,756 @@
+/// The HTML backend renders a deck into a single, self-contained HTML page.
+/// It walks the same mdast tree that `prettify::visit_md_node` walks for the terminal,
+/// maps the colors from the style map to CSS, and embeds a small navigation script.
+use crate::deck::Slide;
+use crate::prettify;
+use crate::style::{Color, Style};
+use crate::utils::{css_color, escape_html, remove_comments, PAUSE};
+
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use markdown::mdast;
+use regex::Regex;
+
+/// The state carried while visiting the mdast tree of a slide.
+struct Context<'a> {
+    style: &'a Style,
+    // The alignment of an open `$[clr]` ... `$[e]` block, if any.
+    block_alignment: Cell<Option<&'static str>>,
+    // The source offsets at which the fragments of the slide start.
+    fragments: Vec<usize>,
+    // The local images of the deck, None when the images are left where they are.
+    assets: Option<&'a Assets>,
+}
+
+/// The local images of a deck, either copied into the `assets` directory next to the page of an
+/// exported deck, or inlined in the page as data URLs.
+pub struct Assets {
+    // The directory the paths of the images are relative to, as in the terminal.
+    base_dir: PathBuf,
+    // Whether the images are inlined in the page rather than copied.
+    inline: bool,
+    // The images, with the path of their copy relative to the page.
+    files: RefCell<Vec<(PathBuf, String)>>,
+}
+
+impl Assets {
+    /// The images of an exported deck, copied next to its page.
+    pub fn new(base_dir: &Path) -> Assets {
+        Assets {
+            base_dir: base_dir.to_path_buf(),
+            inline: false,
+            files: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The images of a self-contained page, inlined as data URLs.
+    pub fn inline(base_dir: &Path) -> Assets {
+        Assets {
+            inline: true,
+            ..Assets::new(base_dir)
+        }
+    }
+
+    /// Returns the address of an image in the deck, and records the local images to copy.
+    /// Remote images and data URLs are kept as they are, and so are the images that cannot be inlined.
+    fn add(&self, url: &str) -> String {
+        if url.is_empty()
+            || url.contains("://")
+            || url.starts_with("//")
+            || url.starts_with("data:")
+        {
+            return url.to_string();
+        }
+        let source = self.base_dir.join(url);
+        if self.inline {
+            return data_url(&source).unwrap_or_else(|| url.to_string());
+        }
+        let mut files = self.files.borrow_mut();
+        if let Some((_, copy)) = files.iter().find(|(path, _)| *path == source) {
+            return copy.clone();
+        }
+
+        // Images of the same name from different directories are numbered.
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+        let mut copy = format!("assets/{}", name);
+        let mut count = 1;
+        while files.iter().any(|(_, taken)| *taken == copy) {
+            count += 1;
+            copy = match name.rsplit_once('.') {
+                Some((stem, extension)) => format!("assets/{}-{}.{}", stem, count, extension),
+                None => format!("assets/{}-{}", name, count),
+            };
+        }
+        files.push((source, copy.clone()));
+        copy
+    }
+
+    /// Returns the images to copy: the path of every image, and the path of its copy relative to the page.
+    pub fn files(&self) -> Vec<(PathBuf, String)> {
+        self.files.borrow().clone()
+    }
+}
+
+/// Returns an image file as a data URL, or None if it cannot be read or is not an image.
+fn data_url(path: &Path) -> Option<String> {
+    let mime = image::ImageFormat::from_path(path).ok()?.to_mime_type();
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Returns the fragment a node is revealed with, 0 when it is shown up front.
+fn fragment_of(node: &mdast::Node, ctx: &Context) -> usize {
+    match node.position() {
+        Some(position) => ctx
+            .fragments
+            .partition_point(|start| *start <= position.start.offset),
+        None => 0,
+    }
+}
+
+fn alignment_from_flag(flag: &str) -> &'static str {
+    match flag {
+        "c" => "center",
+        "r" => "right",
+        _ => "left",
+    }
+}
+
+/// Strips the `$[clr]$`, `$[clr]` and `$[e]` alignment flags from the rendered text of a block
+/// and returns the text-align rule the block should be rendered with.
+fn apply_alignment(text: &str, ctx: &Context) -> (String, Option<&'static str>) {
+    let line_re = Regex::new(r"\$\[([clr])\]\$").unwrap();
+    let block_re = Regex::new(r"\$\[([clr])\]").unwrap();
+    let end_block_re = Regex::new(r"\$\[e\]").unwrap();
+
+    if let Some(captures) = line_re.captures(text) {
+        let alignment = alignment_from_flag(&captures[1]);
+        return (line_re.replace_all(text, "").to_string(), Some(alignment));
+    }
+
+    let mut text = text.to_string();
+    if let Some(captures) = block_re.captures(&text) {
+        ctx.block_alignment
+            .set(Some(alignment_from_flag(&captures[1])));
+        text = block_re.replace_all(&text, "").to_string();
+    }
+
+    let alignment = ctx.block_alignment.get();
+    if end_block_re.is_match(&text) {
+        ctx.block_alignment.set(None);
+        text = end_block_re.replace_all(&text, "").to_string();
+    }
+
+    (text, alignment)
+}
+
+/// Wraps the text of a block element in the given tag, honouring the alignment flags.
+fn block(tag: &str, style: &str, text: &str, ctx: &Context) -> String {
+    let (text, alignment) = apply_alignment(text, ctx);
+    if text.trim().is_empty() {
+        return String::new();
+    }
+    let mut style = style.to_string();
+    if let Some(alignment) = alignment {
+        style.push_str(&format!("text-align: {};", alignment));
+    }
+    if style.is_empty() {
+        format!("<{tag}>{text}</{tag}>\n")
+    } else {
+        format!("<{tag} style=\"{style}\">{text}</{tag}>\n")
+    }
+}
+
+fn visit_children(children: &[mdast::Node], ctx: &Context) -> String {
+    children
+        .iter()
+        .map(|child| visit_md_node(child, ctx))
+        .collect()
+}
+
+/// Recursively visit the mdast tree and return the HTML for it.
+/// This mirrors `prettify::visit_md_node`, the styles are taken from the style map.
+fn visit_md_node(node: &mdast::Node, ctx: &Context) -> String {
+    match node {
+        mdast::Node::Root(root) => root
+            .children
+            .iter()
+            .map(|child| {
+                let html = visit_md_node(child, ctx);
+                match fragment_of(child, ctx) {
+                    // Lists are revealed item by item
+                    fragment
+                        if fragment > 0
+                            && !html.is_empty()
+                            && !matches!(child, mdast::Node::List(_)) =>
+                    {
+                        format!(
+                            "<div class=\"fragment\" data-fragment=\"{}\">{}</div>\n",
+                            fragment, html
+                        )
+                    }
+                    _ => html,
+                }
+            })
+            .collect(),
+
+        mdast::Node::Paragraph(paragraph) => {
+            block("p", "", &visit_children(&paragraph.children, ctx), ctx)
+        }
+
+        mdast::Node::Text(text) => escape_html(&text.value),
+
+        mdast::Node::Heading(heading) => {
+            let (tag, color): (&str, Color) = match heading.depth {
+                1 => ("h1", ctx.style.h1),
+                2 => ("h2", ctx.style.h2),
+                3 => ("h3", ctx.style.h3),
+                4 => ("h4", ctx.style.h4),
+                5 => ("h5", ctx.style.h5),
+                _ => ("h6", ctx.style.h6),
+            };
+            block(
+                tag,
+                &format!("color: {};", css_color(color)),
+                &visit_children(&heading.children, ctx),
+                ctx,
+            )
+        }
+
+        mdast::Node::InlineCode(inline_code) => format!(
+            "<code style=\"color: {}\">{}</code>",
+            css_color(ctx.style.inline_code),
+            escape_html(&inline_code.value)
+        ),
+
+        mdast::Node::Code(code) => {
+            let language = code.lang.as_deref().unwrap_or("plaintext");
+            if ctx.style.syntax_highlighting {
+                prettify::syntax_highlighter_html(language, &code.value, &ctx.style.syntax_theme)
+            } else {
+                format!("<pre><code>{}</code></pre>\n", escape_html(&code.value))
+            }
+        }
+
+        mdast::Node::Emphasis(emphasis) => {
+            format!("<em>{}</em>", visit_children(&emphasis.children, ctx))
+        }
+
+        mdast::Node::Strong(strong) => {
+            format!("<strong>{}</strong>", visit_children(&strong.children, ctx))
+        }
+
+        mdast::Node::Delete(delete) => {
+            format!("<del>{}</del>", visit_children(&delete.children, ctx))
+        }
+
+        mdast::Node::Link(link) => format!(
+            "<a href=\"{}\" style=\"color: {}\">{}</a>",
+            escape_html(&link.url),
+            css_color(ctx.style.link_text),
+            visit_children(&link.children, ctx)
+        ),
+
+        mdast::Node::Image(image) => {
+            let url = match ctx.assets {
+                Some(assets) => assets.add(&image.url),
+                None => image.url.clone(),
+            };
+            format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                escape_html(&url),
+                escape_html(&image.alt)
+            )
+        }
+
+        mdast::Node::ThematicBreak(_) => "<hr>\n".to_string(),
+
+        mdast::Node::BlockQuote(blockquote) => {
+            format!(
+                "<blockquote style=\"color: {}; background: {}\">\n{}</blockquote>\n",
+                css_color(ctx.style.blockquote.foreground),
+                css_color(ctx.style.blockquote.background),
+                visit_children(&blockquote.children, ctx)
+            )
+        }
+
+        mdast::Node::List(list) => {
+            let (tag, bullet_color, text_color) = if list.ordered {
+                ("ol", ctx.style.ordered_list_bullet, ctx.style.ordered_list)
+            } else {
+                (
+                    "ul",
+                    ctx.style.unordered_list_bullet,
+                    ctx.style.unordered_list,
+                )
+            };
+            let start = match list.start {
+                Some(start) if list.ordered => format!(" start=\"{}\"", start),
+                _ => String::new(),
+            };
+            format!(
+                "<{tag}{start} style=\"color: {}; --bullet: {}\">\n{}</{tag}>\n",
+                css_color(text_color),
+                css_color(bullet_color),
+                visit_children(&list.children, ctx)
+            )
+        }
+
+        mdast::Node::ListItem(list_item) => {
+            let mut result = match fragment_of(node, ctx) {
+                0 => String::from("<li>"),
+                fragment => format!("<li class=\"fragment\" data-fragment=\"{}\">", fragment),
+            };
+            // Task list items are rendered with a checkbox
+            match list_item.checked {
+                Some(true) => result.push_str("☑ "),
+                Some(false) => result.push_str("☐ "),
+                None => {}
+            }
+            for child in &list_item.children {
+                // Tight list items are rendered inline, like the terminal renderer does
+                if let mdast::Node::Paragraph(paragraph) = child {
+                    result.push_str(&visit_children(&paragraph.children, ctx));
+                } else {
+                    result.push_str(&visit_md_node(child, ctx));
+                }
+            }
+            result.push_str("</li>\n");
+            result
+        }
+
+        mdast::Node::Break(_) => "<br>\n".to_string(),
+
+        mdast::Node::Definition(definition) => format!(
+            "<p class=\"definition\" style=\"color: {}\">[{}]: {}</p>\n",
+            css_color(ctx.style.definition),
+            escape_html(&definition.identifier),
+            escape_html(&definition.url)
+        ),
+
+        mdast::Node::Table(table) => render_table(table, ctx),
+
+        mdast::Node::FootnoteReference(reference) => format!(
+            "<sup style=\"color: {}\">[{}]</sup>",
+            css_color(ctx.style.definition),
+            escape_html(&reference.identifier)
+        ),
+
+        mdast::Node::FootnoteDefinition(footnote) => {
+            let mut content = String::new();
+            for child in &footnote.children {
+                if let mdast::Node::Paragraph(paragraph) = child {
+                    content.push_str(&visit_children(&paragraph.children, ctx));
+                } else {
+                    content.push_str(&visit_md_node(child, ctx));
+                }
+            }
+            format!(
+                "<p class=\"definition\"><span style=\"color: {}\">[{}]:</span> {}</p>\n",
+                css_color(ctx.style.definition),
+                escape_html(&footnote.identifier),
+                content
+            )
+        }
+
+        // The pauses only split the slide into fragments
+        mdast::Node::Html(html) if PAUSE.is_match(html.value.trim()) => String::new(),
+        mdast::Node::Html(html) => html.value.clone(),
+
+        _ => String::new(),
+    }
+}
+
+/// Renders a table, with the first row as its header.
+/// The cells are aligned according to the alignment of their column.
+fn render_table(table: &mdast::Table, ctx: &Context) -> String {
+    let mut result = String::from("<table>\n");
+    for (i, row) in table.children.iter().enumerate() {
+        let cells = match row {
+            mdast::Node::TableRow(row) => &row.children,
+            _ => continue,
+        };
+        let tag = if i == 0 { "th" } else { "td" };
+        if i == 0 {
+            result.push_str("<thead>\n");
+        } else if i == 1 {
+            result.push_str("<tbody>\n");
+        }
+        result.push_str("<tr>");
+        for (column, cell) in cells.iter().enumerate() {
+            let content = match cell {
+                mdast::Node::TableCell(cell) => visit_children(&cell.children, ctx),
+                cell => visit_md_node(cell, ctx),
+            };
+            let mut style = match table.align.get(column) {
+                Some(mdast::AlignKind::Left) => "text-align: left;".to_string(),
+                Some(mdast::AlignKind::Right) => "text-align: right;".to_string(),
+                Some(mdast::AlignKind::Center) => "text-align: center;".to_string(),
+                _ => String::new(),
+            };
+            if i == 0 {
+                style.push_str(&format!(" color: {};", css_color(ctx.style.table_header)));
+            }
+            result.push_str(&format!(
+                "<{tag} style=\"{}\">{}</{tag}>",
+                style.trim(),
+                content
+            ));
+        }
+        result.push_str("</tr>\n");
+        if i == 0 {
+            result.push_str("</thead>\n");
+        }
+    }
+    if table.children.len() > 1 {
+        result.push_str("</tbody>\n");
+    }
+    result.push_str("</table>\n");
+    result
+}
+
+/// Renders a single slide to HTML.
+/// The comments are removed before rendering, the same way the terminal renderer does.
+/// The blocks after a pause, and the items of incremental lists, are marked as fragments.
+/// The local images are recorded in the assets of an exported deck, and point to their copies.
+pub fn render_slide(
+    slide: &Slide,
+    style: &Style,
+    assets: Option<&Assets>,
+) -> Result<String, Box<dyn Error>> {
+    render_markdown(&slide.contents, style, Some(slide.incremental), assets)
+}
+
+/// Renders the markdown text to HTML.
+/// # Arguments
+/// * `md_text` - The markdown text.
+/// * `style` - The style of the slides.
+/// * `incremental` - Whether the lists are incremental, None when the text is not split into fragments, such as the notes.
+/// * `assets` - The local images of an exported deck, None when the images are left where they are.
+fn render_markdown(
+    md_text: &str,
+    style: &Style,
+    incremental: Option<bool>,
+    assets: Option<&Assets>,
+) -> Result<String, Box<dyn Error>> {
+    let md_text = remove_comments(md_text);
+    let parsed = markdown::to_mdast(&md_text, &markdown::ParseOptions::gfm())
+        .map_err(|err| format!("Error parsing markdown: {}", err))?;
+
+    let fragments = match incremental {
+        Some(incremental) => prettify::fragment_starts(&parsed, incremental, style.clear),
+        None => Vec::new(),
+    };
+    let ctx = Context {
+        style,
+        block_alignment: Cell::new(None),
+        fragments,
+        assets,
+    };
+
+    Ok(visit_md_node(&parsed, &ctx))
+}
+
+/// Returns the stylesheet of the deck, built from the style.
+fn stylesheet(style: &Style) -> String {
+    let highlight_foreground = css_color(style.highlighter.foreground);
+    let highlight_background = css_color(style.highlighter.background);
+
+    let vertical = if style.vertical_alignment {
+        "center"
+    } else {
+        "flex-start"
+    };
+    let horizontal = if style.horizontal_alignment {
+        "center"
+    } else {
+        "flex-start"
+    };
+    let border = if style.boxed {
+        "1px solid currentColor"
+    } else {
+        "none"
+    };
+    let progress = if style.progress { "block" } else { "none" };
+    let table_border = css_color(style.table_border);
+
+    format!(
+        "
+    html, body {{ margin: 0; height: 100%; background: #1e1e1e; color: #e5e5e5; }}
+    body {{ font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 20px; }}
+    .slide {{ display: none; box-sizing: border-box; height: 100vh; padding: 2em; align-items: {vertical}; justify-content: {horizontal}; }}
+    .slide.active {{ display: flex; }}
+    .content {{ max-width: 90vw; max-height: 90vh; overflow: auto; padding: 1em 2em; border: {border}; }}
+    h1::before {{ content: '█ '; }} h2::before {{ content: '██ '; }} h3::before {{ content: '███ '; }}
+    h4::before {{ content: '████ '; }} h5::before {{ content: '█████ '; }} h6::before {{ content: '██████ '; }}
+    h1, h2, h3, h4, h5, h6 {{ font-size: 1em; }}
+    blockquote {{ margin: 1em 0; padding: 0.25em 1em; }}
+    li::marker {{ color: var(--bullet); }}
+    pre {{ padding: 0.5em 1em; overflow-x: auto; }}
+    a {{ text-decoration: none; }}
+    img {{ max-width: 100%; }}
+    hr {{ border: none; border-top: 1px dashed currentColor; }}
+    table {{ border-collapse: collapse; margin: 1em 0; }}
+    th, td {{ border: 1px solid {table_border}; padding: 0.25em 0.75em; }}
+    ::selection {{ color: {highlight_foreground}; background: {highlight_background}; }}
+    #progress {{ display: {progress}; position: fixed; left: 1em; bottom: 1em; opacity: 0.7; }}
+    .notes {{ display: none; }}
+    .fragment {{ visibility: hidden; }}
+    .fragment.visible {{ visibility: visible; }}
+"
+    )
+}
+
+/// The navigation script of the deck.
+/// The keys mirror the terminal presenter: `l`/right arrow for the next fragment or slide and
+/// `h`/left arrow for the previous one. The current slide is kept in the location hash.
+/// `s` opens the speaker view in a new window, with the notes, the next slide and a timer. The
+/// speaker view follows the deck, and the deck can be driven from either window.
+const SCRIPT: &str = "
+    const slides = document.querySelectorAll('.slide');
+    const progress = document.getElementById('progress');
+    let current = 0;
+    let revealed = 0;
+    let speaker = null;
+    let started = null;
+
+    function fragments(index) {
+        return slides[index].querySelectorAll('.content .fragment');
+    }
+
+    function fragmentCount(index) {
+        return Math.max(0, ...Array.from(fragments(index), (fragment) => Number(fragment.dataset.fragment)));
+    }
+
+    function reveal(count) {
+        revealed = Math.max(0, Math.min(fragmentCount(current), count));
+        fragments(current).forEach((fragment) =>
+            fragment.classList.toggle('visible', Number(fragment.dataset.fragment) <= revealed));
+        updateSpeaker();
+    }
+
+    function next() {
+        if (revealed < fragmentCount(current)) reveal(revealed + 1);
+        else if (current < slides.length - 1) show(current + 1);
+    }
+
+    function previous() {
+        if (revealed > 0) reveal(revealed - 1);
+        else if (current > 0) show(current - 1, Infinity);
+    }
+
+    function show(index, count = 0) {
+        current = Math.max(0, Math.min(slides.length - 1, index));
+        slides.forEach((slide, i) => slide.classList.toggle('active', i === current));
+        const title = slides[current].dataset.title;
+        progress.textContent = `[${current + 1}/${slides.length}]` + (title ? ` ${title}` : '');
+        history.replaceState(null, '', `#${current + 1}`);
+        reveal(count);
+    }
+
+    function slideContent(index) {
+        const slide = slides[index];
+        return slide ? slide.querySelector('.content').innerHTML : '<p>End of the deck</p>';
+    }
+
+    function updateSpeaker() {
+        if (!speaker || speaker.closed) return;
+        const view = speaker.document;
+        view.getElementById('current').innerHTML = slideContent(current);
+        view.getElementById('next').innerHTML = slideContent(current + 1);
+        const notes = slides[current].querySelector('.notes');
+        view.getElementById('notes').innerHTML = notes ? notes.innerHTML : '<p class=\"empty\">No notes for this slide</p>';
+        view.getElementById('position').textContent = `${current + 1}/${slides.length}`;
+    }
+
+    function tick() {
+        if (!speaker || speaker.closed) return;
+        const elapsed = Math.floor((Date.now() - started) / 1000);
+        const pad = (value) => String(value).padStart(2, '0');
+        speaker.document.getElementById('timer').textContent =
+            `${pad(Math.floor(elapsed / 3600))}:${pad(Math.floor(elapsed / 60) % 60)}:${pad(elapsed % 60)}`;
+        speaker.document.getElementById('clock').textContent = new Date().toLocaleTimeString();
+    }
+
+    function openSpeaker() {
+        if (speaker && !speaker.closed) {
+            speaker.focus();
+            return;
+        }
+        speaker = window.open('', 'dough-speaker', 'width=1100,height=750');
+        if (!speaker) return;
+        started = started || Date.now();
+        const view = speaker.document;
+        view.open();
+        view.write(`<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Speaker view - ${document.title}</title>
+            <style>${document.querySelector('style').textContent}
+            body { display: grid; grid-template-columns: 3fr 2fr; grid-template-rows: auto 1fr 1fr; gap: 1em; padding: 1em; box-sizing: border-box; height: 100vh; }
+            header { grid-column: 1 / 3; display: flex; gap: 2em; opacity: 0.8; }
+            .pane { overflow: auto; border: 1px solid #555; padding: 0.5em 1em; }
+            .label { font-size: 0.7em; opacity: 0.6; }
+            #current-pane { grid-row: 2 / 4; }
+            #next { font-size: 0.7em; }
+            #next .fragment { visibility: visible; }
+            #current .fragment:not(.visible) { visibility: visible; opacity: 0.35; }
+            .empty { opacity: 0.5; }
+            </style></head><body>
+            <header><span id=\"position\"></span><span>elapsed <span id=\"timer\">00:00:00</span></span><span id=\"clock\"></span></header>
+            <div class=\"pane\" id=\"current-pane\"><div class=\"label\">current</div><div id=\"current\"></div></div>
+            <div class=\"pane\"><div class=\"label\">next</div><div id=\"next\"></div></div>
+            <div class=\"pane\"><div class=\"label\">notes</div><div id=\"notes\"></div></div>
+            </body></html>`);
+        view.close();
+        view.addEventListener('keydown', handleKey);
+        speaker.setInterval(tick, 1000);
+        updateSpeaker();
+        tick();
+    }
+
+    function handleKey(event) {
+        switch (event.key) {
+            case 'ArrowRight': case 'l': case 'L': case ' ': case 'PageDown':
+                next(); break;
+            case 'ArrowLeft': case 'h': case 'H': case 'PageUp':
+                previous(); break;
+            case 'Home':
+                show(0); break;
+            case 'End':
+                show(slides.length - 1); break;
+            case 's': case 'S':
+                openSpeaker(); break;
+            default:
+                return;
+        }
+        event.preventDefault();
+    }
+
+    document.addEventListener('keydown', handleKey);
+
+    show((parseInt(location.hash.slice(1), 10) || 1) - 1);
+";
+
+/// The live reload script of the deck, used when the deck is served by `dough serve`.
+/// The page reloads when the server sends an event, the location hash keeps the current slide.
+const LIVE_RELOAD_SCRIPT: &str = "
+    const events = new EventSource('/events');
+    events.onmessage = () => location.reload();
+";
+
+/// Renders all the slides of a deck into one self-contained HTML page, with its local images inlined.
+/// # Arguments
+/// * `title` - The title of the page.
+/// * `slides` - The slides of the deck, in order.
+/// * `style` - The style of the slides.
+/// * `base_dir` - The directory the paths of the images are relative to.
+pub fn render_deck(
+    title: &str,
+    slides: &[Slide],
+    style: &Style,
+    base_dir: &Path,
+) -> Result<String, Box<dyn Error>> {
+    render_page(
+        title,
+        slides,
+        style,
+        SCRIPT,
+        Some(&Assets::inline(base_dir)),
+    )
+}
+
+/// Renders all the slides of a deck into one HTML page, for a deck exported to a directory.
+/// The local images point to their copies in the `assets` directory, which are recorded in `assets`.
+/// # Arguments
+/// * `title` - The title of the page.
+/// * `slides` - The slides of the deck, in order.
+/// * `style` - The style of the slides.
+/// * `assets` - Collects the local images of the deck.
+pub fn render_export(
+    title: &str,
+    slides: &[Slide],
+    style: &Style,
+    assets: &Assets,
+) -> Result<String, Box<dyn Error>> {
+    render_page(title, slides, style, SCRIPT, Some(assets))
+}
+
+/// Renders all the slides of a deck into an HTML page that reloads itself when the deck changes.
+/// # Arguments
+/// * `title` - The title of the page.
+/// * `slides` - The slides of the deck, in order.
+/// * `style` - The style of the slides.
+pub fn render_live_deck(
+    title: &str,
+    slides: &[Slide],
+    style: &Style,
+) -> Result<String, Box<dyn Error>> {
+    render_page(
+        title,
+        slides,
+        style,
+        &format!("{}{}", SCRIPT, LIVE_RELOAD_SCRIPT),
+        None,
+    )
+}
+
+fn render_page(
+    title: &str,
+    slides: &[Slide],
+    style: &Style,
+    script: &str,
+    assets: Option<&Assets>,
+) -> Result<String, Box<dyn Error>> {
+    let mut sections = String::new();
+    for (i, slide) in slides.iter().enumerate() {
+        let title = match &slide.title {
+            Some(title) => format!(" data-title=\"{}\"", escape_html(title)),
+            None => String::new(),
+        };
+        // The notes are hidden in the deck, and shown in the speaker view
+        let notes = if slide.notes.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<aside class=\"notes\">\n{}</aside>",
+                render_markdown(&slide.notes, style, None, assets)?
+            )
+        };
+        sections.push_str(&format!(
+            "<section class=\"slide\" id=\"slide-{}\"{}><div class=\"content\">\n{}</div>{}</section>\n",
+            i + 1,
+            title,
+            render_slide(slide, style, assets)?,
+            notes
+        ));
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+<style>{}</style>
+</head>
+<body>
+{}<div id=\"progress\"></div>
+<script>{}</script>
+</body>
+</html>
+",
+        escape_html(title),
+        stylesheet(style),
+        sections,
+        script
+    ))
+}
@@ -0,0 +1,367 @@
+/// The keymap binds the keys of the terminal presenter to its actions.
+/// The bindings are read from the `keys:` section of the style.yml file, which maps an action to
+/// one key or a list of keys. Actions missing from the section keep their default keys, and a key
+/// bound in the section is taken away from the action it is bound to by default.
+///
+/// ```yaml
+/// keys:
+///   next: [l, right, pagedown, "."]
+///   previous: [h, left, pageup, b]
+///   run_code: ["&", "é", "\"", "'", "("]
+/// ```
+use std::collections::{BTreeMap, HashMap};
+
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use termion::event::Key;
+
+/// An action of the presenter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Next,
+    Previous,
+    ScrollUp,
+    ScrollDown,
+    ToggleHighlight,
+    Refresh,
+    Quit,
+    /// Stop the presenter and go back to the shell, until it is continued with `fg`.
+    Suspend,
+    Jump,
+    Overview,
+    Notes,
+    /// Run a code block, the first key runs the first block, the second key the second block, and so on.
+    RunCode,
+    StopCode,
+    ClosePane,
+    PaneUp,
+    PaneDown,
+}
+
+impl Action {
+    /// The name of the action in the style.yml file.
+    fn name(self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Previous => "previous",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollDown => "scroll_down",
+            Action::ToggleHighlight => "toggle_highlight",
+            Action::Refresh => "refresh",
+            Action::Quit => "quit",
+            Action::Suspend => "suspend",
+            Action::Jump => "jump",
+            Action::Overview => "overview",
+            Action::Notes => "notes",
+            Action::RunCode => "run_code",
+            Action::StopCode => "stop_code",
+            Action::ClosePane => "close_pane",
+            Action::PaneUp => "pane_up",
+            Action::PaneDown => "pane_down",
+        }
+    }
+}
+
+/// The keys bound in the style.yml file, by action.
+#[derive(Clone, Debug, Default)]
+pub struct KeyBindings(BTreeMap<Action, Vec<Key>>);
+
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Keys {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let entries = BTreeMap::<Action, Keys>::deserialize(deserializer)?;
+        let mut bindings = BTreeMap::new();
+        let mut bound: HashMap<Key, Action> = HashMap::new();
+        for (action, keys) in entries {
+            let names = match keys {
+                Keys::One(name) => vec![name],
+                Keys::Many(names) => names,
+            };
+            let mut keys = Vec::new();
+            for name in names {
+                let key = parse_key(&name).map_err(de::Error::custom)?;
+                if let Some(other) = bound.insert(key, action) {
+                    return Err(de::Error::custom(format!(
+                        "`{}` is bound to both {} and {}",
+                        name,
+                        other.name(),
+                        action.name()
+                    )));
+                }
+                keys.push(key);
+            }
+            bindings.insert(action, keys);
+        }
+        Ok(KeyBindings(bindings))
+    }
+}
+
+/// Returns the keys an action is bound to by default.
+fn default_keys(action: Action) -> Vec<Key> {
+    use Key::*;
+
+    match action {
+        Action::Next => vec![Char('l'), Char('L'), Right],
+        Action::Previous => vec![Char('h'), Char('H'), Left],
+        Action::ScrollUp => vec![Char('k'), Char('K'), Up],
+        Action::ScrollDown => vec![Char('j'), Char('J'), Down],
+        Action::ToggleHighlight => vec![Char('t')],
+        Action::Refresh => vec![Ctrl('r')],
+        Action::Quit => vec![Char('q'), Char('Q'), Esc, Ctrl('c')],
+        Action::Suspend => vec![Ctrl('z')],
+        Action::Jump => vec![Char('g'), Char('G')],
+        Action::Overview => vec![Char('o'), Char('O')],
+        Action::Notes => vec![Char('n'), Char('N')],
+        Action::RunCode => ('1'..='9').map(Char).collect(),
+        Action::StopCode => vec![Char('x'), Char('X')],
+        Action::ClosePane => vec![Char('c'), Char('C')],
+        Action::PaneUp => vec![PageUp],
+        Action::PaneDown => vec![PageDown],
+    }
+}
+
+/// Every action, in the order of the `Action` enum.
+const ACTIONS: [Action; 16] = [
+    Action::Next,
+    Action::Previous,
+    Action::ScrollUp,
+    Action::ScrollDown,
+    Action::ToggleHighlight,
+    Action::Refresh,
+    Action::Quit,
+    Action::Suspend,
+    Action::Jump,
+    Action::Overview,
+    Action::Notes,
+    Action::RunCode,
+    Action::StopCode,
+    Action::ClosePane,
+    Action::PaneUp,
+    Action::PaneDown,
+];
+
+/// The actions of the keys, with the default keys and the keys of the style.yml file.
+pub struct Keymap {
+    actions: HashMap<Key, Action>,
+    // The code block each key bound to run_code runs, starting at 1.
+    code_blocks: HashMap<Key, usize>,
+    // The keys, in the order they are bound, so that the keys of an action are listed in that order.
+    keys: Vec<Key>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the keys bound in the style.yml file.
+    pub fn new(bindings: &KeyBindings) -> Keymap {
+        let mut keymap = Keymap {
+            actions: HashMap::new(),
+            code_blocks: HashMap::new(),
+            keys: Vec::new(),
+        };
+
+        // The defaults go first, so that the keys of the style.yml file replace them.
+        let defaults = ACTIONS
+            .iter()
+            .filter(|action| !bindings.0.contains_key(action))
+            .map(|action| (*action, default_keys(*action)));
+        let bound = bindings
+            .0
+            .iter()
+            .map(|(action, keys)| (*action, keys.clone()));
+        for (action, keys) in defaults.chain(bound) {
+            for (i, key) in keys.into_iter().enumerate() {
+                keymap.code_blocks.remove(&key);
+                if action == Action::RunCode {
+                    keymap.code_blocks.insert(key, i + 1);
+                }
+                if keymap.actions.insert(key, action).is_none() {
+                    keymap.keys.push(key);
+                }
+            }
+        }
+        keymap
+    }
+
+    /// Returns the action a key is bound to.
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.actions.get(key).copied()
+    }
+
+    /// Returns the keys bound to an action, in the order they are bound.
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.keys
+            .iter()
+            .filter(|key| self.action(key) == Some(action))
+            .copied()
+            .collect()
+    }
+
+    /// Returns the code block a key runs, starting at 1, when the key is bound to run_code.
+    pub fn code_block(&self, key: &Key) -> Option<usize> {
+        self.code_blocks.get(key).copied()
+    }
+}
+
+/// Parse the name of a key, as written in the style.yml file.
+/// A key is a single character, such as `l` or `.`, a named key, such as `right`, `pagedown`,
+/// `space` or `esc`, a function key from `f1` to `f12`, or a character with a modifier, such as
+/// `ctrl+r` or `alt+n`.
+fn parse_key(name: &str) -> Result<Key, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Char(c));
+    }
+
+    let lower = name.trim().to_lowercase();
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(format!("unknown key `{}`", name)),
+        }
+    };
+    if let Some(rest) = lower.strip_prefix("ctrl+") {
+        return single(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = name.trim().get(4..).filter(|_| lower.starts_with("alt+")) {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=12).contains(&n) {
+            return Ok(Key::F(n));
+        }
+    }
+
+    let key = match lower.as_str() {
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "backtab" => Key::BackTab,
+        "esc" | "escape" => Key::Esc,
+        "space" => Key::Char(' '),
+        "enter" | "return" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        _ => return Err(format!("unknown key `{}`", name)),
+    };
+    Ok(key)
+}
+
+/// Returns the name of a key, as written in the style.yml file.
+pub fn key_name(key: &Key) -> String {
+    match key {
+        Key::Char(' ') => "space".to_string(),
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl+{}", c),
+        Key::Alt(c) => format!("alt+{}", c),
+        Key::F(n) => format!("f{}", n),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::BackTab => "backtab".to_string(),
+        Key::Esc => "esc".to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(text: &str) -> Result<KeyBindings, String> {
+        serde_yaml::from_str(text).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn parse_key_names() {
+        assert_eq!(parse_key("l"), Ok(Key::Char('l')));
+        assert_eq!(parse_key("é"), Ok(Key::Char('é')));
+        assert_eq!(parse_key("PageDown"), Ok(Key::PageDown));
+        assert_eq!(parse_key("space"), Ok(Key::Char(' ')));
+        assert_eq!(parse_key("enter"), Ok(Key::Char('\n')));
+        assert_eq!(parse_key("f12"), Ok(Key::F(12)));
+        assert_eq!(parse_key("Ctrl+R"), Ok(Key::Ctrl('r')));
+        assert_eq!(parse_key("alt+N"), Ok(Key::Alt('N')));
+    }
+
+    #[test]
+    fn parse_unknown_keys() {
+        assert_eq!(parse_key("f13"), Err("unknown key `f13`".to_string()));
+        assert_eq!(
+            parse_key("ctrl+rr"),
+            Err("unknown key `ctrl+rr`".to_string())
+        );
+        assert_eq!(parse_key("hyper"), Err("unknown key `hyper`".to_string()));
+    }
+
+    #[test]
+    fn key_bound_to_two_actions() {
+        let err = bindings("next: [l, space]\nprevious: [h, space]\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.contains("`space` is bound to both next and previous"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn unknown_action() {
+        assert!(bindings("fly: f").is_err());
+    }
+
+    #[test]
+    fn bound_keys_replace_defaults() {
+        let keymap = Keymap::new(&bindings("next: space\nquit: l\n").unwrap());
+        assert_eq!(keymap.action(&Key::Char(' ')), Some(Action::Next));
+        assert_eq!(keymap.action(&Key::Char('l')), Some(Action::Quit));
+        assert_eq!(keymap.action(&Key::Right), None);
+        assert_eq!(keymap.action(&Key::Char('h')), Some(Action::Previous));
+    }
+
+    #[test]
+    fn keys_of_an_action_in_order() {
+        let keymap = Keymap::new(&bindings("next: [space, right]\nquit: l\n").unwrap());
+        assert_eq!(keymap.keys(Action::Next), vec![Key::Char(' '), Key::Right]);
+        assert_eq!(keymap.keys(Action::Quit), vec![Key::Char('l')]);
+        assert_eq!(keymap.keys(Action::Previous)[0], Key::Char('h'));
+        let names: Vec<String> = keymap.keys(Action::Next).iter().map(key_name).collect();
+        assert_eq!(names, vec!["space", "right"]);
+        assert_eq!(key_name(&Key::Ctrl('r')), "ctrl+r");
+    }
+
+    #[test]
+    fn run_code_keys_by_block() {
+        let keymap = Keymap::new(&KeyBindings::default());
+        assert_eq!(keymap.code_block(&Key::Char('3')), Some(3));
+
+        let keymap = Keymap::new(&bindings("run_code: [\"&\", \"é\"]\nnext: \"1\"\n").unwrap());
+        assert_eq!(keymap.code_block(&Key::Char('é')), Some(2));
+        assert_eq!(keymap.code_block(&Key::Char('1')), None);
+        assert_eq!(keymap.action(&Key::Char('1')), Some(Action::Next));
+    }
+}
@@ -1,23 +1,46 @@
+mod control;
+mod deck;
+mod graphics;
+mod grid;
+mod html;
+mod keymap;
+mod overview;
+mod pane;
+mod pdf;
 mod prettify;
 extern crate termion;
 mod ramen;
+mod recorder;
+mod remote;
+mod server;
+mod signals;
+mod style;
+mod svg;
+mod terminal;
 mod utils;
+mod watcher;
+use crate::keymap::{Action, Keymap};
+use crate::pane::{NotesPane, OutputPane};
 use crate::ramen::run_code;
-use crate::utils::{remove_comments, remove_last_n_lines};
+use crate::signals::Signal;
+use crate::style::Style;
+use crate::terminal::TerminalGuard;
+use crate::utils::{clip_line, remove_comments, remove_last_n_lines, visible_width};
 
 use std::error::Error;
 use std::fmt;
 use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
 
+use colored::*;
 use paris::Logger;
 
-use std::collections::HashMap;
-use std::io::{stdin, stdout, Result, Write};
-use std::process::exit;
+use std::io::{stdin, Result, Write};
 use termion::event::Key;
 use termion::input::TermRead;
-use termion::raw::IntoRawMode;
 
 // All the possible navigation actions when presenting a project.
 enum NavigationAction {
@@ -27,14 +50,111 @@ enum NavigationAction {
     None,
     ScrollUp,
     ScrollDown,
+    // Render the slide again from the start, with Ctrl+R.
     Refresh,
+    // Render the slide again, keeping the scroll/highlight position: its file or the style changed,
+    // the terminal was resized, a pane was opened or closed, or the presenter continued after it was suspended.
+    Reload,
     ToggleHighlight,
+    // Move to a slide, by its number, with its first fragments revealed.
+    Goto(u32, usize),
+    // Ask for the number or the title of a slide to move to.
+    Jump,
+    // Pick the slide to move to from a grid of the slides.
+    Overview,
 }
 
+/// Which view of the deck a terminal shows.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Screen {
+    /// The slides, driven from the keyboard.
+    Audience,
+    /// The control view of the presenter, with the notes, the previews and the clock. It leads the followers.
+    Presenter,
+    /// The slides, driven by the presenter view of the same project.
+    Follower,
+}
+
+/// How a presentation in the terminal is recorded.
+pub struct Recording {
+    /// The asciicast file the presentation is written to.
+    pub path: PathBuf,
+    /// Whether the output of the code blocks run during the presentation is part of the recording.
+    pub code_output: bool,
+}
+
+// The events the presenter waits on while a slide is shown.
+enum PresenterEvent {
+    // A key was pressed.
+    Key(Key),
+    // A file in the project directory was added, modified or removed.
+    Changed(PathBuf),
+    // A line printed by a running code block.
+    Output(ramen::Stream, String),
+    // A code block finished running, with its result or the reason it did not run.
+    Finished(usize, std::result::Result<ramen::RunResult, String>),
+    // A message from the presenter view, received by its followers.
+    Remote(remote::Message),
+    // A second passed, the clock of the presenter view is redrawn.
+    Tick,
+    // The terminal was resized, the slide is laid out again for its new size.
+    Resize,
+    // The presenter was asked to stop with SIGTSTP. It gives the terminal back while it is stopped, and draws the screen again when it continues.
+    Suspend,
+    // Another thread of the presenter panicked, with its message. The presentation ends with the message as its error.
+    Panic(String),
+}
+
+// The slide shown by the terminal presenter, and its place in the deck.
+struct SlideState<'a> {
+    // The slide, used for its contents, title and notes, and to refresh it when its file or one of its images changes.
+    slide: &'a deck::Slide,
+    // The number of the slide, starting at 1.
+    number: u32,
+    // The number of slides in the deck.
+    total: usize,
+    // The number of fragments of the slide that are revealed.
+    revealed: usize,
+}
+
+// What the terminal presenter shows around the slides, and the channels it reacts to.
+struct PresenterState<'a> {
+    // The key presses, file changes and code output the presenter reacts to.
+    events: &'a Receiver<PresenterEvent>,
+    // Sends the output of the code blocks run from the slide to the presenter.
+    sender: &'a Sender<PresenterEvent>,
+    // The output pane, shown below the slide.
+    pane: &'a mut OutputPane,
+    // The notes pane, shown below the slide.
+    notes: &'a mut NotesPane,
+    // Which view of the deck the terminal shows.
+    screen: Screen,
+}
+
+// What the control view of the presenter drives and keeps track of, beside the slides.
+struct ControlContext<'a> {
+    // The events the presenter waits on.
+    events: &'a Receiver<PresenterEvent>,
+    // Sends the keys to the followers.
+    broadcaster: &'a remote::Broadcaster,
+    // When the presentation started.
+    started: Instant,
+}
+
+// The smallest terminal a slide is shown in, narrower slides are still given this many columns.
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 3;
+
+// The size of the terminal the slides of a document are laid out for, as columns and rows.
+const EXPORT_VIEWPORT: (u16, u16) = (100, 30);
+
 // Define a struct to hold project information.
+#[derive(Clone)]
 pub struct Project {
     fs_path: std::path::PathBuf,
     template: std::path::PathBuf,
+    // The markdown file of a single-file deck, when the project is presented from one file.
+    deck_file: Option<std::path::PathBuf>,
 }
 
 // Define a custom error type.
@@ -66,13 +186,27 @@ impl Project {
     /// A new project instance.
 
     pub fn new(name: &str, workdir: &str, template: &str) -> Project {
+        let path = std::path::Path::new(&workdir).join(name);
+
+        // A markdown file can be presented on its own, the project directory is then the directory of the file.
+        let (fs_path, deck_file) = if path.is_file() {
+            let dir = path
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_else(|| std::path::PathBuf::from(workdir));
+            (dir, Some(path))
+        } else {
+            (path, None)
+        };
+
         // Use the template provided to copy the template files into the project directory.
         Project {
-            fs_path: std::path::Path::new(&workdir).join(name).to_path_buf(),
+            fs_path,
             template: std::path::Path::new(workdir)
                 .join("templates")
                 .join(template)
                 .to_path_buf(),
+            deck_file,
         }
     }
     /// Initialize a project.
@@ -134,44 +268,79 @@ impl Project {
             fs::copy(file_path, output_path)?;
         }
 
+        // If the template has a manifest, copy it along with every slide it lists.
+        // Slides outside the template directory are shared between decks, and are not copied.
+        let manifest = self.template.join(deck::MANIFEST_FILE);
+        if manifest.exists() {
+            let files = deck::manifest_files(&manifest).map_err(|err| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string())
+            })?;
+            fs::copy(&manifest, self.fs_path.join(deck::MANIFEST_FILE))?;
+
+            for file in files {
+                let shared = file.is_absolute()
+                    || file
+                        .components()
+                        .any(|component| component == std::path::Component::ParentDir);
+                if shared {
+                    log.warn(format!(
+                        "Slide {} is outside the template and was not copied",
+                        file.display()
+                    ));
+                    continue;
+                }
+                let output_path = self.fs_path.join(&file);
+                if let Some(parent) = output_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(self.template.join(&file), output_path)?;
+            }
+        }
+
         return Ok(());
     }
 
     /// Renders the project in terminal mode.
     /// # Arguments
     /// * `self` - The project instance.
-    /// file_contents - The contents of the file to be rendered.
-    /// style_map - The style map used to describe the style of the slides.
-    /// render - A boolean indicating whether to render the slide or not.
-    /// lines - The number of lines to be rendered.
+    /// * `style` - The style of the slides.
+    /// * `highlight` - A boolean indicating whether the lines are highlighted rather than scrolled.
+    /// * `render` - A boolean indicating whether to render the slide or not.
+    /// * `lines` - The number of lines to be rendered.
+    /// * `current` - The slide being rendered, its number and the fragments revealed.
+    /// * `presenter` - The events the presenter reacts to, and the panes shown below the slide.
     /// # Returns
     /// A result containing a tuple of the navigation action and the number of lines to be rendered.
 
     fn render_term(
         self: &Self,
-        file_contents: &str,
-        style_map: &HashMap<String, String>,
+        style: &Style,
         highlight: bool,
         render: bool,
         lines: &mut u32,
-        current_slide: u32,
+        current: &SlideState,
+        presenter: &mut PresenterState,
     ) -> std::result::Result<(NavigationAction, u32), Box<dyn Error>> {
-        // Used to check whether all the lines will be rendered or will it be rendered one by one.
-        // Based on the config in the style map
-        let clear = if style_map.get("clear").unwrap() == "true" {
-            true
-        } else {
-            false
-        };
+        // The comments are removed from the contents of the slide.
+        let contents = remove_comments(&current.slide.contents);
+        // The fragments of the slide that are not revealed yet are concealed.
+        prettify::set_reveal(current.revealed, current.slide.incremental);
 
-        let boxed = if style_map.get("box").unwrap() == "true" {
-            true
-        } else {
-            false
-        };
+        // The keys are bound to the actions in the style.yml file, or have their default actions.
+        let keymap = Keymap::new(&style.keys);
 
-        // The custom Paris logger, used to log the progress of the presentation.
-        let mut log = Logger::new();
+        // The rows of the output and notes panes are kept free of the slide.
+        let (width, height) = termion::terminal_size()?;
+        let notes_height = presenter.notes.height(
+            &current.slide.notes,
+            width,
+            height - presenter.pane.height(height),
+        );
+        prettify::reserve_rows(presenter.pane.height(height) + notes_height);
+        // The images of the slide are drawn within the rows of the slide.
+        let slide_height = height - presenter.pane.height(height) - notes_height;
+
+        let boxed = style.boxed;
 
         let slide;
 
@@ -185,7 +354,7 @@ impl Project {
         // The highlight mode
         if highlight {
             // The slide is rendered according to the rendered lines.
-            slide = prettify::prettify(&file_contents.to_string(), &style_map, line_number)?;
+            slide = prettify::prettify(&contents, style, line_number)?;
 
             // The bounds are used to determine the number of lines to be rendered.
             // This code implements infinte scrolling while highlighting.
@@ -198,17 +367,12 @@ impl Project {
                 line_number = upper_bound - lower_bound - 1;
             }
             // The slide is rendered here
-            print!("{}", slide);
-
-            // if clear is true, the slide is cleared after rendering, enabling users to scroll down lines one by one
-            if render && clear {
-                return Ok((NavigationAction::ToggleHighlight, line_number));
-            }
+            Self::print_slide(&slide, width, slide_height)?;
         }
         // The scroll mode
         else {
             // The slide is rendered  with `0 lines` lines to be highlighted.
-            slide = prettify::prettify(&file_contents.to_string(), &style_map, 0)?;
+            slide = prettify::prettify(&contents, style, 0)?;
             // The bounds are used to determine the number of lines to be rendered, and the scrolling range.
             let (upper_bound, lower_bound) = prettify::get_bounds();
 
@@ -232,122 +396,479 @@ impl Project {
 
             // the render condition implies that a fresh slide is rendered.
             if render {
-                if clear {
-                    // If clear is true, all the lines are removed while rendering, enabling users to scroll down lines one by one.
-                    line_number = slide.lines().count() as u32;
-                    print!("{}", remove_last_n_lines(&slide, line_number));
-                } else {
-                    // If clear is false, the entire slide is rendered.
-                    print!("{}", slide);
-                }
+                Self::print_slide(&slide, width, slide_height)?;
             } else {
                 // if the render is false, the slide is being scrolled and the last n lines are removed.
-                print!("{}", remove_last_n_lines(&slide, line_number));
+                Self::print_slide(
+                    &remove_last_n_lines(&slide, line_number),
+                    width,
+                    slide_height,
+                )?;
             }
         }
         // The progress implies the number of slides that have been rendered/ the total number of slides.
-        // It is rendered based on the config in the style map.
-        match style_map.get("progress").unwrap().as_str() {
-            "true" => {
-                print!("\r");
-                log.info(format!(
-                    "[{}/{}]",
-                    current_slide,
-                    fs::read_dir(&self.fs_path)?.count() - 1
-                ));
-            }
-            _ => {}
+        // It is rendered based on the config in the style.
+        // It is printed like the info lines of the logger, through the output of the recorder.
+        let mut stdout = recorder::output();
+        if style.progress {
+            let progress = match &current.slide.title {
+                Some(title) => format!("[{}/{}] {}", current.number, current.total, title),
+                None => format!("[{}/{}]", current.number, current.total),
+            };
+            writeln!(stdout, "\r{} {}", "ℹ".cyan(), progress)?;
         }
 
         // The stdout is flushed to ensure that the slide is rendered properly.
-        let stdin = stdin();
-        let mut stdout = stdout().into_raw_mode()?;
+        // The output of the code blocks is printed apart, so that a recording can leave it out.
+        let mut code_output = recorder::code_output();
 
         stdout.flush()?;
+        presenter.notes.draw(
+            &mut stdout,
+            &current.slide.notes,
+            width,
+            slide_height + 1,
+            notes_height,
+        )?;
+        presenter.pane.draw(&mut code_output, width, height)?;
 
         // The navigation actions are handled here.
         // The navigation actions are:
-        // 1. Next - Move to the next slide.
-        // 2. Previous - Move to the previous slide.
+        // 1. Next - Reveal the next fragment of the slide, or move to the next slide.
+        // 2. Previous - Conceal the last fragment of the slide, or move to the previous slide.
         // 3. Exit - Exit the presentation.
         // 4. ScrollUp - Scroll up the slide.
         // 5. ScrollDown - Scroll down the slide.
         // 6. ToggleHighlight - Toggle between highlighting and scrolling modes.
         // 7. Refresh - Refresh the slide.
         // 8. RunCode - Run the code block in the slide acc to the order of thei appearance.
-        // 9. None - Do nothing.
-
-        // TODO: Add a watcher here, any changes will call NavigationAction::Refresh
+        //    x stops all the running code blocks.
+        //    The output is streamed into the output pane, which is scrolled with PageUp/PageDown and closed with c.
+        // 9. ToggleNotes - Show or hide the speaker notes of the slide, with n.
+        // 10. Jump - Move to a slide by its number or its title, with g.
+        // 11. Overview - Pick a slide from the grid of the slides, with o.
+        // 12. None - Do nothing.
+        // A change to the current slide or to the style.yml file refreshes the slide without a key press.
+        // A resize of the terminal lays the slide out again, keeping the scroll/highlight position.
+        // Ctrl+Z suspends the presenter, which gives the terminal back to the shell until `fg`.
+        // A follower is driven by the keys of the presenter view, its own keys only quit or refresh.
+        // It does not run or stop the code blocks for the presenter view.
 
-        for c in stdin.keys() {
-            match c? {
-                Key::Right | Key::Char('l') | Key::Char('L') => {
-                    return Ok((NavigationAction::Next, line_number))
+        for event in presenter.events.iter() {
+            let key = match event {
+                PresenterEvent::Key(key) if presenter.screen == Screen::Follower => {
+                    match keymap.action(&key) {
+                        Some(Action::Quit) | Some(Action::Refresh) | Some(Action::Suspend) => key,
+                        _ => continue,
+                    }
                 }
-                Key::Left | Key::Char('h') | Key::Char('H') => {
-                    return Ok((NavigationAction::Previous, line_number));
+                PresenterEvent::Key(key) => key,
+                // The code blocks only run in the presenter view, a follower does not run the code it is sent.
+                PresenterEvent::Remote(remote::Message::Key(key)) => match keymap.action(&key) {
+                    Some(Action::RunCode) | Some(Action::StopCode) => continue,
+                    _ => key,
+                },
+                PresenterEvent::Remote(remote::Message::Slide(slide, fragment)) => {
+                    if (slide, fragment) != (current.number, current.revealed) {
+                        return Ok((NavigationAction::Goto(slide, fragment), line_number));
+                    }
+                    continue;
+                }
+                PresenterEvent::Tick => continue,
+                // The scroll/highlight position is kept, the resizes the slide was already laid out for are skipped.
+                PresenterEvent::Resize => {
+                    if termion::terminal_size()? != (width, height) {
+                        return Ok((NavigationAction::Reload, line_number));
+                    }
+                    continue;
                 }
-                //add escape and ctrl + c here
-                Key::Char('q') | Key::Char('Q') => {
-                    return Ok((NavigationAction::Exit, line_number))
+                PresenterEvent::Suspend => {
+                    terminal::suspend()?;
+                    return Ok((NavigationAction::Reload, line_number));
                 }
-                Key::Esc | Key::Ctrl('c') => return Ok((NavigationAction::Exit, line_number)),
-                Key::Up | Key::Char('k') | Key::Char('K') => {
-                    return Ok((NavigationAction::ScrollUp, line_number))
+                PresenterEvent::Panic(message) => return Err(Box::new(DoughError(message))),
+                PresenterEvent::Changed(path) => {
+                    let image = current
+                        .slide
+                        .images()
+                        .iter()
+                        .any(|image| self.fs_path.join(image) == path);
+                    if path == current.slide.path
+                        || image
+                        || path == self.fs_path.join("style.yml")
+                        || path == self.fs_path.join(deck::MANIFEST_FILE)
+                    {
+                        return Ok((NavigationAction::Reload, line_number));
+                    }
+                    continue;
                 }
-                Key::Down | Key::Char('j') | Key::Char('J') => {
-                    return Ok((NavigationAction::ScrollDown, line_number))
+                // The output of the code blocks only redraws the pane.
+                PresenterEvent::Output(stream, line) => {
+                    presenter.pane.output(stream, &line);
+                    presenter.pane.draw(&mut code_output, width, height)?;
+                    continue;
                 }
-                Key::Char('t') => return Ok((NavigationAction::ToggleHighlight, line_number)),
-                Key::Ctrl('r') => return Ok((NavigationAction::Refresh, line_number)),
-                Key::Char(c) if ('0'..='9').contains(&c) => {
-                    let mut log = Logger::new();
-                    let style_map_clone = style_map.clone(); // Clone the style_map for the new thread
-                    let c_num = (c as u8 - '0' as u8) as usize;
+                PresenterEvent::Finished(block, result) => {
+                    presenter.pane.finish(block, &result);
+                    presenter.pane.draw(&mut code_output, width, height)?;
+                    continue;
+                }
+            };
+            match keymap.action(&key) {
+                Some(Action::Next) => return Ok((NavigationAction::Next, line_number)),
+                Some(Action::Previous) => return Ok((NavigationAction::Previous, line_number)),
+                Some(Action::Quit) => return Ok((NavigationAction::Exit, line_number)),
+                Some(Action::Suspend) => {
+                    // The terminal is given back until the presenter is continued, and the slide drawn again.
+                    terminal::suspend()?;
+                    return Ok((NavigationAction::Reload, line_number));
+                }
+                Some(Action::ScrollUp) => return Ok((NavigationAction::ScrollUp, line_number)),
+                Some(Action::ScrollDown) => return Ok((NavigationAction::ScrollDown, line_number)),
+                Some(Action::ToggleHighlight) => {
+                    return Ok((NavigationAction::ToggleHighlight, line_number))
+                }
+                Some(Action::Jump) => return Ok((NavigationAction::Jump, line_number)),
+                Some(Action::Overview) => return Ok((NavigationAction::Overview, line_number)),
+                Some(Action::Notes) => {
+                    // The slide is laid out again around the notes.
+                    presenter.notes.toggle();
+                    return Ok((NavigationAction::Reload, line_number));
+                }
+                Some(Action::Refresh) => return Ok((NavigationAction::Refresh, line_number)),
+                Some(Action::StopCode) => {
+                    // Stops the code blocks that are still running.
+                    ramen::stop_all();
+                    continue;
+                }
+                Some(Action::PaneUp) => {
+                    presenter
+                        .pane
+                        .scroll_up(presenter.pane.height(height) as usize / 2, height);
+                    presenter.pane.draw(&mut code_output, width, height)?;
+                    continue;
+                }
+                Some(Action::PaneDown) => {
+                    presenter
+                        .pane
+                        .scroll_down(presenter.pane.height(height) as usize / 2);
+                    presenter.pane.draw(&mut code_output, width, height)?;
+                    continue;
+                }
+                Some(Action::ClosePane) if presenter.pane.is_visible() => {
+                    // The slide is laid out again without the rows of the pane.
+                    presenter.pane.dismiss();
+                    return Ok((NavigationAction::Reload, line_number));
+                }
+                Some(Action::RunCode) => {
+                    // The keys bound to run_code run the blocks in the order of their appearance.
+                    let c_num = match keymap.code_block(&key) {
+                        Some(block) => block,
+                        None => continue,
+                    };
+                    // The runners are cloned from the style for the new thread
+                    let config = ramen::Config {
+                        runners: style.runtime_map.clone(),
+                        limits: ramen::Limits {
+                            timeout: Duration::from_secs_f64(style.code_timeout),
+                            compile_timeout: Duration::from_secs_f64(style.compile_timeout),
+                            memory: style.code_memory_limit,
+                        },
+                        rust_edition: style.rust_edition.clone(),
+                    };
+                    let sender = presenter.sender.clone();
                     thread::Builder::new()
                         .name("ramen:".to_string())
                         .spawn(move || {
-                            let output = Self::run_code(c_num, style_map_clone);
-                            match output {
-                                Ok(output) => {
-                                    log.success(format!("\r{}:", c_num));
-                                    output.lines().for_each(|line| println!("\r{}", line));
-                                    print!("\n");
-                                }
-                                Err(e) => {
-                                    log.error(format!("\r{} : {} ", c_num, e.to_string()));
-                                }
-                            }
+                            // Every line is sent to the presenter as soon as it is printed.
+                            let output_sender = sender.clone();
+                            let result = Self::run_code(c_num, config, move |stream, line| {
+                                let _ = output_sender
+                                    .send(PresenterEvent::Output(stream, line.to_string()));
+                            });
+                            let _ = sender.send(PresenterEvent::Finished(
+                                c_num,
+                                result.map_err(|e| e.to_string()),
+                            ));
                         })
                         .expect("Failed to spawn thread");
+
+                    // The slide is laid out again when the pane opens, otherwise only the pane is drawn.
+                    let opened = !presenter.pane.is_visible();
+                    presenter.pane.start(c_num);
+                    if opened {
+                        return Ok((NavigationAction::Reload, line_number));
+                    }
+                    presenter.pane.draw(&mut code_output, width, height)?;
                     continue;
                 }
                 _ => continue,
             }
         }
 
-        drop(stdout);
-
         return Ok((NavigationAction::None, line_number));
     }
 
+    /// Render the control view of the presenter, and wait for a navigation action.
+    /// The keys of the presenter are forwarded to the followers, which run the code blocks and scroll the slides.
+    /// # Arguments
+    /// * `style` - The style of the slides.
+    /// * `slides` - The slides of the deck.
+    /// * `current_slide` - The number of the current slide, starting at 1.
+    /// * `revealed` - The number of fragments of the current slide that are revealed.
+    /// * `context` - The events the presenter waits on, the followers and the clock.
+    /// # Returns
+    /// The navigation action, along with the lines to be rendered, which the control view does not change.
+    fn render_control(
+        &self,
+        style: &Style,
+        slides: &[deck::Slide],
+        current_slide: u32,
+        revealed: usize,
+        context: &ControlContext,
+    ) -> std::result::Result<(NavigationAction, u32), Box<dyn Error>> {
+        let (width, height) = termion::terminal_size()?;
+        let layout = control::Layout::new(width, height);
+        let index = current_slide as usize - 1;
+
+        // The previews are laid out for their part of the terminal, images are drawn with half blocks.
+        let preview_style = Style {
+            image_protocol: graphics::Protocol::Blocks,
+            ..style.clone()
+        };
+        // The next slide is shown whole, and laid out first, so that the fragments counted are those of the current slide.
+        prettify::reserve_rows(0);
+        prettify::set_viewport(Some((layout.preview_width, layout.preview_height)));
+        let next = slides
+            .get(index + 1)
+            .map(|slide| {
+                prettify::set_reveal(usize::MAX, slide.incremental);
+                prettify::prettify(&remove_comments(&slide.contents), &preview_style, 0)
+            })
+            .transpose();
+        prettify::set_reveal(revealed, slides[index].incremental);
+        let current =
+            prettify::prettify(&remove_comments(&slides[index].contents), &preview_style, 0);
+        prettify::set_viewport(None);
+        let (current, next) = (current?, next?);
+
+        let view = control::ControlView {
+            current: &current,
+            next: next.as_deref(),
+            notes: &slides[index].notes,
+            position: current_slide,
+            total: slides.len(),
+            title: slides[index].title.as_deref(),
+            started: context.started,
+        };
+        let keymap = Keymap::new(&style.keys);
+        let mut stdout = recorder::output();
+        control::draw(&mut stdout, &view, width, height)?;
+
+        for event in context.events.iter() {
+            match event {
+                PresenterEvent::Key(key) => {
+                    let action = keymap.action(&key);
+                    // The notes are only shown to the presenter, and the followers move to the slide the presenter picks.
+                    if !matches!(
+                        action,
+                        Some(Action::Notes)
+                            | Some(Action::Jump)
+                            | Some(Action::Overview)
+                            | Some(Action::Suspend)
+                    ) {
+                        context.broadcaster.send_key(&key);
+                    }
+                    match action {
+                        Some(Action::Next) => return Ok((NavigationAction::Next, 1)),
+                        Some(Action::Previous) => return Ok((NavigationAction::Previous, 1)),
+                        Some(Action::Quit) => return Ok((NavigationAction::Exit, 1)),
+                        Some(Action::Suspend) => {
+                            terminal::suspend()?;
+                            return Ok((NavigationAction::Reload, 1));
+                        }
+                        Some(Action::Refresh) => return Ok((NavigationAction::Refresh, 1)),
+                        Some(Action::Jump) => return Ok((NavigationAction::Jump, 1)),
+                        Some(Action::Overview) => return Ok((NavigationAction::Overview, 1)),
+                        _ => continue,
+                    }
+                }
+                PresenterEvent::Changed(path) => {
+                    let shown = slides[index..]
+                        .iter()
+                        .take(2)
+                        .any(|slide| slide.path == path);
+                    if shown
+                        || path == self.fs_path.join("style.yml")
+                        || path == self.fs_path.join(deck::MANIFEST_FILE)
+                    {
+                        return Ok((NavigationAction::Reload, 1));
+                    }
+                }
+                PresenterEvent::Tick => control::draw_header(&mut stdout, &view, width)?,
+                PresenterEvent::Resize => {
+                    if termion::terminal_size()? != (width, height) {
+                        return Ok((NavigationAction::Reload, 1));
+                    }
+                }
+                PresenterEvent::Suspend => {
+                    terminal::suspend()?;
+                    return Ok((NavigationAction::Reload, 1));
+                }
+                PresenterEvent::Panic(message) => return Err(Box::new(DoughError(message))),
+                _ => continue,
+            }
+        }
+
+        Ok((NavigationAction::None, 1))
+    }
+
+    /// Ask for the slide to move to on the last row of the terminal, by its number or a part of its title.
+    /// The output of the code blocks keeps going to the output pane while the prompt is open.
+    /// # Arguments
+    /// * `titles` - The titles of the slides.
+    /// * `current_slide` - The number of the current slide, starting at 1.
+    /// * `events` - The events the presenter waits on.
+    /// * `pane` - The output pane.
+    /// # Returns
+    /// The number of the slide to move to, or None if the prompt was closed.
+    fn jump_prompt(
+        titles: &[String],
+        current_slide: u32,
+        events: &Receiver<PresenterEvent>,
+        pane: &mut OutputPane,
+    ) -> std::result::Result<Option<u32>, Box<dyn Error>> {
+        let mut stdout = recorder::output();
+        let mut input = String::new();
+        let mut error: Option<String> = None;
+
+        loop {
+            let (width, height) = termion::terminal_size()?;
+            overview::draw_prompt(&mut stdout, &input, error.as_deref(), width, height)?;
+
+            let key = match events.recv()? {
+                PresenterEvent::Key(key) => key,
+                PresenterEvent::Output(stream, line) => {
+                    pane.output(stream, &line);
+                    continue;
+                }
+                PresenterEvent::Finished(block, result) => {
+                    pane.finish(block, &result);
+                    continue;
+                }
+                // The slide behind the prompt is gone once the presenter continues, the prompt is closed to draw it again.
+                PresenterEvent::Suspend => {
+                    terminal::suspend()?;
+                    return Ok(None);
+                }
+                PresenterEvent::Panic(message) => return Err(Box::new(DoughError(message))),
+                _ => continue,
+            };
+            match key {
+                Key::Char('\n') if input.trim().is_empty() => return Ok(None),
+                Key::Char('\n') => {
+                    match overview::find_slide(&input, titles, current_slide as usize - 1) {
+                        Ok(index) => return Ok(Some(index as u32 + 1)),
+                        Err(e) => error = Some(e),
+                    }
+                }
+                Key::Esc | Key::Ctrl('c') => return Ok(None),
+                Key::Backspace => {
+                    input.pop();
+                    error = None;
+                }
+                Key::Char(c) if !c.is_control() => {
+                    input.push(c);
+                    error = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Show the grid of the slides, and let the presenter pick a slide with the keys of the keymap.
+    /// The keys of the previous and next slide move the selection sideways, the keys of the scrolling up and down,
+    /// `Enter` opens the selected slide, and the keys of quit and of the overview close it.
+    /// # Arguments
+    /// * `titles` - The titles of the slides.
+    /// * `current_slide` - The number of the current slide, which is selected first.
+    /// * `style` - The style of the slides.
+    /// * `events` - The events the presenter waits on.
+    /// * `pane` - The output pane.
+    /// # Returns
+    /// The number of the slide picked, or None if the overview was closed.
+    fn pick_slide(
+        titles: &[String],
+        current_slide: u32,
+        style: &Style,
+        events: &Receiver<PresenterEvent>,
+        pane: &mut OutputPane,
+    ) -> std::result::Result<Option<u32>, Box<dyn Error>> {
+        let mut stdout = recorder::output();
+        let mut selected = (current_slide as usize - 1).min(titles.len().saturating_sub(1));
+        let keymap = Keymap::new(&style.keys);
+
+        loop {
+            let (width, height) = termion::terminal_size()?;
+            overview::draw(&mut stdout, titles, selected, style, &keymap, width, height)?;
+
+            let key = match events.recv()? {
+                PresenterEvent::Key(key) => key,
+                PresenterEvent::Output(stream, line) => {
+                    pane.output(stream, &line);
+                    continue;
+                }
+                PresenterEvent::Finished(block, result) => {
+                    pane.finish(block, &result);
+                    continue;
+                }
+                // The overview is drawn again once the presenter continues.
+                PresenterEvent::Suspend => {
+                    terminal::suspend()?;
+                    continue;
+                }
+                PresenterEvent::Panic(message) => return Err(Box::new(DoughError(message))),
+                _ => continue,
+            };
+            match (key, keymap.action(&key)) {
+                (Key::Char('\n'), _) => return Ok(Some(selected as u32 + 1)),
+                (Key::Esc, _) | (_, Some(Action::Quit)) | (_, Some(Action::Overview)) => {
+                    return Ok(None)
+                }
+                (key, action) => {
+                    selected = overview::move_selection(
+                        selected,
+                        key,
+                        action,
+                        titles.len(),
+                        overview::columns(width),
+                    )
+                }
+            }
+        }
+    }
+
     /// Run the code block in the slide.
     /// # Arguments
     /// * `num` - The number of the code block in the slide.
-    /// * `env_map` - The environment map used to run the code.
+    /// * `config` - How the code is run: the runners of the languages and the limits.
+    /// * `on_output` - Called with every line the code block prints, while it runs.
     /// # Returns
-    /// A result containing the output of the code block.
-
-    fn run_code(
+    /// A result containing the output, the errors and the exit status of the code block.
+    fn run_code<F>(
         num: usize,
-        env_map: HashMap<String, String>,
-    ) -> std::result::Result<String, Box<dyn Error>> {
+        config: ramen::Config,
+        on_output: F,
+    ) -> std::result::Result<ramen::RunResult, Box<dyn Error>>
+    where
+        F: Fn(ramen::Stream, &str) + Send + Sync + 'static,
+    {
         // The langugage and the code are obtained from the slide.
         let res = prettify::get_code(num);
         match res {
             // If the code block is found, the code is run.
             Ok((lang, code)) => {
-                let res = run_code(lang, code, &env_map);
+                let res = run_code(lang, code, &config, on_output);
                 match res {
                     // The output of the code block is returned.
                     Ok(output) => Ok(output),
@@ -358,19 +879,403 @@ impl Project {
         }
     }
 
+    /// Read the style of the project from its style.yml file.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// # Returns
+    /// A result containing the style of the slides, or an error describing the invalid keys or values.
+    fn read_style(&self) -> std::result::Result<Style, Box<dyn Error>> {
+        Ok(Style::load(&self.fs_path.join("style.yml"))?)
+    }
+
+    /// The file the deck is read from, either a manifest or a single-file deck.
+    /// This is the file the project was created from, the deck.yml manifest of the project directory,
+    /// or the deck.md file of a project directory without numbered slides.
+    fn deck_file(&self) -> Option<PathBuf> {
+        if self.deck_file.is_some() {
+            return self.deck_file.clone();
+        }
+        let manifest = self.fs_path.join(deck::MANIFEST_FILE);
+        if manifest.exists() {
+            return Some(manifest);
+        }
+        let deck_file = self.fs_path.join(deck::DECK_FILE);
+        if !self.fs_path.join("1.md").exists() && deck_file.exists() {
+            return Some(deck_file);
+        }
+        None
+    }
+
+    /// Read the deck of the project.
+    /// A manifest lists the slides in order, a single-file deck is split on the `slide_separator` of
+    /// the style map, otherwise the numbered slides are read until the first missing slide.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// * `style` - The style of the slides.
+    /// # Returns
+    /// A result containing the deck.
+    fn read_deck(&self, style: &Style) -> std::result::Result<deck::Deck, Box<dyn Error>> {
+        let deck = match self.deck_file() {
+            Some(deck_file) if deck::is_manifest(&deck_file) => deck::read_manifest(&deck_file)?,
+            Some(deck_file) => deck::Deck {
+                title: None,
+                slides: deck::read_file(&deck_file, &style.slide_separator)?,
+            },
+            None => deck::Deck {
+                title: None,
+                slides: deck::read_numbered(&self.fs_path)?,
+            },
+        };
+
+        if deck.slides.is_empty() {
+            return Err(Box::new(DoughError(
+                "No slides found in the project".into(),
+            )));
+        }
+        Ok(deck)
+    }
+
+    /// The title of the project.
+    /// This is the title set in the manifest, or the name of the deck file or the project directory.
+    fn title(&self, deck: &deck::Deck) -> String {
+        if let Some(title) = &deck.title {
+            return title.clone();
+        }
+        let path = self.deck_file().unwrap_or_else(|| self.fs_path.clone());
+        path.file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "dough".to_string())
+    }
+
+    /// The files the watcher polls for changes.
+    /// A project directory is watched as a whole. A deck presented from a file is watched through
+    /// its own files only: the file, the slides of a manifest, the style and the local images.
+    fn watched(&self) -> watcher::Watched {
+        if self.deck_file.is_none() {
+            return watcher::Watched::Dir(self.fs_path.clone());
+        }
+        let project = self.clone();
+        watcher::Watched::Files(Box::new(move || {
+            let mut files = vec![
+                project.fs_path.join("style.yml"),
+                project.fs_path.join(deck::MANIFEST_FILE),
+            ];
+            files.extend(project.deck_file.clone());
+            let style = project.read_style().unwrap_or_default();
+            if let Ok(deck) = project.read_deck(&style) {
+                for slide in &deck.slides {
+                    files.push(slide.path.clone());
+                    files.extend(
+                        slide
+                            .images()
+                            .iter()
+                            .map(|image| project.fs_path.join(image)),
+                    );
+                }
+            }
+            files.sort();
+            files.dedup();
+            files
+        }))
+    }
+
+    /// Create the style.yml file of the project if it does not exist.
+    fn ensure_style(&self) -> std::result::Result<(), Box<dyn Error>> {
+        if !self.fs_path.join("style.yml").exists() {
+            let mut log = Logger::new();
+            log.warn("Style config not found. Using default styles");
+            utils::create_style(self.fs_path.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Present a project in HTML mode.
+    /// The slides are rendered into a single, self-contained `deck.html` file in the project directory,
+    /// which can be opened in any browser and navigated with the keyboard.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// # Returns
+    /// A result containing the path of the generated HTML file.
+    pub fn present_html(&self) -> std::result::Result<std::path::PathBuf, Box<dyn Error>> {
+        self.ensure_style()?;
+        let style = self.read_style()?;
+        let deck = self.read_deck(&style)?;
+        let title = self.title(&deck);
+
+        // A deck presented from a file is written next to it, with the same name.
+        let output_name = match &self.deck_file {
+            Some(deck_file) => deck_file.with_extension("html"),
+            None => PathBuf::from("deck.html"),
+        };
+        let deck = html::render_deck(&title, &deck.slides, &style, &self.fs_path)?;
+        let output_path = self.fs_path.join(output_name);
+        fs::write(&output_path, deck)?;
+
+        Ok(output_path)
+    }
+
+    /// Export a project to a directory, as an `index.html` page and an `assets` directory with its images.
+    /// The page embeds the styles, the highlighted code and the navigation script, so the exported
+    /// deck works offline, from any location.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// * `output_dir` - The directory to write the deck to. It is created if it does not exist.
+    /// # Returns
+    /// A result containing the path of the page.
+    pub fn export(
+        &self,
+        output_dir: &std::path::Path,
+    ) -> std::result::Result<PathBuf, Box<dyn Error>> {
+        if !self.fs_path.is_dir() {
+            return Err(Box::new(DoughError("Project not found".into())));
+        }
+        self.ensure_style()?;
+        let style = self.read_style()?;
+        let deck = self.read_deck(&style)?;
+        let title = self.title(&deck);
+
+        let assets = html::Assets::new(&self.fs_path);
+        let page = html::render_export(&title, &deck.slides, &style, &assets)?;
+        fs::create_dir_all(output_dir)?;
+        let output_path = output_dir.join("index.html");
+        fs::write(&output_path, page)?;
+
+        // An image that cannot be copied is reported, the rest of the deck is still exported.
+        let mut log = Logger::new();
+        for (source, copy) in assets.files() {
+            let destination = output_dir.join(&copy);
+            if let Some(dir) = destination.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            if let Err(err) = fs::copy(&source, &destination) {
+                log.warn(format!(
+                    "Could not copy the image {}: {}",
+                    source.display(),
+                    err
+                ));
+            }
+        }
+
+        Ok(output_path)
+    }
+
+    /// Export a project to a PDF document, with one page per slide.
+    /// The slides are laid out as in a terminal of 100 columns and 30 rows, with the colors of the
+    /// style, and every slide is shown whole.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// * `output` - The path of the document. Its directory is created if it does not exist.
+    /// # Returns
+    /// A result containing the path of the document.
+    pub fn export_pdf(
+        &self,
+        output: &std::path::Path,
+    ) -> std::result::Result<PathBuf, Box<dyn Error>> {
+        if !self.fs_path.is_dir() {
+            return Err(Box::new(DoughError("Project not found".into())));
+        }
+        self.ensure_style()?;
+        let style = self.read_style()?;
+        let deck = self.read_deck(&style)?;
+        let title = self.title(&deck);
+
+        let grids = self.render_grids(&style, &deck.slides)?;
+        if let Some(dir) = output.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (document, missing) = pdf::write_deck(&title, &grids);
+        fs::write(output, document)?;
+        // The standard fonts only have the Latin-1 characters, the others are written as `?`.
+        if !missing.is_empty() {
+            Logger::new().warn(format!(
+                "{} characters have no glyph in the PDF fonts and are shown as `?`: {}",
+                missing.len(),
+                missing.iter().collect::<String>()
+            ));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
+    /// Export a project to a directory of SVG images, one per slide, as the terminal shows them.
+    /// The slides are laid out as for the PDF document, and named by their number, `slide-01.svg` and on.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// * `output_dir` - The directory to write the images to. It is created if it does not exist.
+    /// # Returns
+    /// A result containing the path of the directory.
+    pub fn export_svg(
+        &self,
+        output_dir: &std::path::Path,
+    ) -> std::result::Result<PathBuf, Box<dyn Error>> {
+        if !self.fs_path.is_dir() {
+            return Err(Box::new(DoughError("Project not found".into())));
+        }
+        self.ensure_style()?;
+        let style = self.read_style()?;
+        let deck = self.read_deck(&style)?;
+
+        let grids = self.render_grids(&style, &deck.slides)?;
+        fs::create_dir_all(output_dir)?;
+        // The numbers are padded, so that the images are listed in the order of the slides.
+        let digits = grids.len().to_string().len().max(2);
+        for (i, grid) in grids.iter().enumerate() {
+            let name = format!("slide-{:0width$}.svg", i + 1, width = digits);
+            fs::write(output_dir.join(name), svg::write_slide(grid))?;
+        }
+
+        Ok(output_dir.to_path_buf())
+    }
+
+    /// Lay out the slides of a document or of images as grids of cells, the size of the export viewport.
+    /// Every slide is shown whole, and its images are drawn with half blocks.
+    fn render_grids(
+        &self,
+        style: &Style,
+        slides: &[deck::Slide],
+    ) -> std::result::Result<Vec<grid::Grid>, Box<dyn Error>> {
+        let style = Style {
+            image_protocol: graphics::Protocol::Blocks,
+            ..style.clone()
+        };
+        let (cols, rows) = EXPORT_VIEWPORT;
+        // The slides keep their colors when the output is not a terminal, such as in a build script.
+        colored::control::set_override(true);
+        prettify::set_base_dir(&self.fs_path);
+        prettify::reserve_rows(0);
+        prettify::set_viewport(Some(EXPORT_VIEWPORT));
+        let grids = slides
+            .iter()
+            .map(|slide| {
+                prettify::set_reveal(usize::MAX, slide.incremental);
+                let text = prettify::prettify(&remove_comments(&slide.contents), &style, 0)?;
+                Ok(grid::Grid::parse(&text, cols as usize, rows as usize))
+            })
+            .collect();
+        prettify::set_viewport(None);
+        colored::control::unset_override();
+        grids
+    }
+
+    /// Serve a project as a live HTML presentation on localhost.
+    /// The deck is rendered again on every request, and the browser reloads whenever a slide
+    /// or the style.yml file changes.
+    /// # Arguments
+    /// * `self` - The project instance.
+    /// * `port` - The port to listen on.
+    /// # Returns
+    /// A result indicating whether the server ran successfully or not.
+    pub fn serve(&self, port: u16) -> std::result::Result<(), Box<dyn Error>> {
+        if !self.fs_path.is_dir() {
+            return Err(Box::new(DoughError("Project not found".into())));
+        }
+        self.ensure_style()?;
+
+        // The last page rendered is served while the deck or the style is invalid, such as while it is being edited.
+        let project = self.clone();
+        let last_page: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+        server::serve(self.fs_path.clone(), self.watched(), port, move || {
+            let render = || -> std::result::Result<String, Box<dyn Error>> {
+                let style = project.read_style()?;
+                let deck = project.read_deck(&style)?;
+                let title = project.title(&deck);
+                html::render_live_deck(&title, &deck.slides, &style)
+            };
+            let mut last_page = last_page.lock().unwrap();
+            match render() {
+                Ok(page) => {
+                    *last_page = Some(page.clone());
+                    Ok(page)
+                }
+                Err(err) => match &*last_page {
+                    Some(page) => {
+                        Logger::new().warn(format!(
+                            "Could not render the deck, the last valid version is served: {}",
+                            err
+                        ));
+                        Ok(page.clone())
+                    }
+                    None => Err(err),
+                },
+            }
+        })
+    }
+
+    /// Print a slide, or a notice when the terminal is too small for it.
+    /// A slide fits when none of its lines is wider than the terminal, and a few of its rows are shown.
+    /// Slides taller than the terminal are not too small, they are scrolled.
+    /// # Arguments
+    /// * `slide` - The slide, laid out for the terminal.
+    /// * `width` - The width of the terminal.
+    /// * `height` - The rows of the terminal the slide is shown in.
+    /// # Returns
+    /// A result indicating whether the slide or the notice was printed.
+    fn print_slide(
+        slide: &str,
+        width: u16,
+        height: u16,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let needed = slide
+            .lines()
+            .map(visible_width)
+            .max()
+            .unwrap_or(0)
+            .max(MIN_WIDTH as usize);
+        if needed <= width as usize && height >= MIN_HEIGHT {
+            graphics::print_slide(&mut recorder::output(), slide, height)?;
+            return Ok(());
+        }
+
+        let mut notice = vec!["Terminal too small".bold().to_string()];
+        if needed > width as usize {
+            notice.push(format!("{} columns, the slide needs {}", width, needed));
+        }
+        if height < MIN_HEIGHT {
+            notice.push(format!("{} rows, the slide needs {}", height, MIN_HEIGHT));
+        }
+        notice.push("Resize the terminal to show it".dimmed().to_string());
+
+        let mut stdout = recorder::output();
+        let top = (height.saturating_sub(notice.len() as u16) / 2).max(1);
+        for (row, line) in notice.iter().enumerate() {
+            let line = clip_line(line, width as usize, "");
+            let left = (width.saturating_sub(visible_width(&line) as u16) / 2).max(1);
+            write!(
+                stdout,
+                "{}{}",
+                termion::cursor::Goto(left, top + row as u16),
+                line
+            )?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
     /// This clears the terminal.
     fn clear() {
-        let mut stdout = stdout();
-        write!(stdout, "\x1B[2J\x1B[1;1H").unwrap();
+        let mut stdout = recorder::output();
+        write!(
+            stdout,
+            "{}{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1)
+        )
+        .unwrap();
     }
 
     /// Present a project in terminal mode.
     /// # Arguments
     /// * `self` - The project instance.
+    /// * `screen` - Whether the terminal shows the slides, the presenter view, or follows the presenter view.
+    /// * `recording` - Where the presentation is recorded, if it is.
     /// # Returns
     /// A result indicating whether the project was presented successfully or not.
 
-    pub fn present_term(self: &Self) -> std::result::Result<(), Box<dyn Error>> {
+    pub fn present_term(
+        self: &Self,
+        screen: Screen,
+        recording: Option<Recording>,
+    ) -> std::result::Result<(), Box<dyn Error>> {
         // The custom Paris logger, used to log the progress of the presentation.
         let mut log = Logger::new();
         // Used to determine whether to highlight the code or scroll.
@@ -378,10 +1283,75 @@ impl Project {
         // Used to determine whether to render a new slide or not. Used for scrolling.
         let mut render = true;
         let mut current_slide = 1;
+        // The number of fragments of the current slide that are revealed.
+        let mut revealed: usize = 0;
 
         // The number of lines to be rendered.
         let mut lines: u32 = 1;
 
+        // Key presses are read in a separate thread, so that the presenter can also react to file changes.
+        let (sender, events) = channel();
+        let key_sender = sender.clone();
+        thread::Builder::new()
+            .name("keys".to_string())
+            .spawn(move || {
+                for key in stdin().keys().flatten() {
+                    if key_sender.send(PresenterEvent::Key(key)).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        // The code blocks run from the slides send their output to the presenter.
+        let output_sender = sender.clone();
+        // The output pane is kept across renders, and closed when moving to another slide.
+        let mut pane = OutputPane::default();
+        // The notes pane stays open from one slide to the next.
+        let mut notes = NotesPane::default();
+
+        // The presenter view leads the followers over the socket of the project, and redraws its clock every second.
+        // A follower receives the slide and the keys of the presenter view as events.
+        let socket = remote::socket_path(&self.fs_path)?;
+        let broadcaster = match screen {
+            Screen::Presenter => {
+                let tick_sender = sender.clone();
+                thread::Builder::new()
+                    .name("clock".to_string())
+                    .spawn(move || loop {
+                        thread::sleep(Duration::from_secs(1));
+                        if tick_sender.send(PresenterEvent::Tick).is_err() {
+                            break;
+                        }
+                    })?;
+                Some(remote::Broadcaster::listen(&socket)?)
+            }
+            Screen::Follower => {
+                let remote_sender = sender.clone();
+                remote::follow(socket, move |message| {
+                    remote_sender.send(PresenterEvent::Remote(message)).is_ok()
+                });
+                None
+            }
+            Screen::Audience => None,
+        };
+        let started = Instant::now();
+
+        // A resize of the terminal is sent to the presenter, which lays the slide out again right away.
+        // A presenter asked to stop with SIGTSTP is suspended by the presenter thread, as with Ctrl+Z, so that nothing is drawn while the terminal is given back.
+        let panic_sender = sender.clone();
+        let signal_sender = sender.clone();
+        signals::watch(move |signal| match signal {
+            Signal::Resize => signal_sender.send(PresenterEvent::Resize).is_ok(),
+            Signal::Suspend => signal_sender.send(PresenterEvent::Suspend).is_ok(),
+        })?;
+
+        // Any change to the files of the deck is sent to the presenter, which refreshes the slide if needed.
+        watcher::watch(self.watched(), move |path| {
+            sender
+                .send(PresenterEvent::Changed(path.to_path_buf()))
+                .is_ok()
+        });
+
         // Check if the project directory has style.yml file
         let style_path = self.fs_path.join("style.yml");
         if !style_path.exists() {
@@ -398,63 +1368,153 @@ impl Project {
         // The loop is exited when the user exits the presentation.
         // It handles the navigation actions.
 
+        // The style is used to describe the style of the slides.
+        let mut style = self.read_style()?;
+
+        // The images of the slides are relative to the project directory.
+        prettify::set_base_dir(&self.fs_path);
+
+        // The deck must be valid to start the presentation. It is read again on every render.
+        let mut deck = self.read_deck(&style)?;
+
+        // The presentation runs on the alternate screen, the terminal is given back as it was when the guard is dropped,
+        // on every way out of the presentation.
+        let guard = TerminalGuard::new()?;
+        // A panic in another thread ends the presentation, the terminal is given back before it is reported.
+        terminal::on_panic(move |message| {
+            let _ = panic_sender.send(PresenterEvent::Panic(message));
+        });
+
+        // The recording starts on the blank alternate screen, every frame printed from here on is recorded.
+        if let Some(recording) = &recording {
+            recorder::start(&recording.path, &self.title(&deck), recording.code_output)?;
+        }
+
         loop {
             Self::clear();
-            print!("{}", termion::cursor::Hide);
-            let file_path = self.fs_path.join(format!("{}.md", current_slide));
 
-            if !file_path.exists() {
-                if current_slide == 1 {
-                    return Err(Box::new(DoughError(
-                        "No slides found in the project".into(),
-                    )));
-                }
-                print!("{}", termion::cursor::Show);
-                print!("{}", termion::clear::All);
-                println!("Thank you :)");
-                exit(0)
+            // The style is read again on every render, so that changes to style.yml are picked up.
+            // An invalid style.yml is reported, and the last valid style is kept while it is being edited.
+            match self.read_style() {
+                Ok(new_style) => style = new_style,
+                Err(e) => {
+                    log.warn(e.to_string());
+                }
+            }
+            // The players of the recordings draw text only, the images are recorded as half blocks.
+            if recording.is_some() {
+                style.image_protocol = graphics::Protocol::Blocks;
             }
-            // The contents of the file are read and the comments are removed.
-            let file_contents = fs::read_to_string(&file_path)?;
-            let contents = remove_comments(&file_contents);
-
-            // The style map is used to describe the style of the slides.
-            let style_content = fs::read_to_string(self.fs_path.join("style.yml"))?;
-            let style_map: HashMap<String, String> = style_content
-                .lines()
-                .filter_map(|line| {
-                    let mut parts = line.splitn(2, ':');
-                    Some((
-                        parts.next()?.trim().to_string(),
-                        parts.next()?.trim().to_string(),
-                    ))
-                })
-                .collect();
 
+            // The slides are read on every render, so that changes to the deck are picked up.
+            // An invalid deck.yml or a missing slide is reported, and the last valid deck is kept while it is being edited.
+            match self.read_deck(&style) {
+                Ok(new_deck) => deck = new_deck,
+                Err(e) => {
+                    log.warn(e.to_string());
+                }
+            }
+            let slides = &deck.slides;
+            // A deck that shrank while it was being edited is shown from its last slide.
+            current_slide = current_slide.clamp(1, slides.len() as u32);
+            let slide = &slides[current_slide as usize - 1];
             // The navigation actions are handled here.
             // TODO: Pass a mutable reference of the lines to be rendered to the render_term function, instead of returning it and updating it here.
 
-            match Self::render_term(
-                self,
-                &contents,
-                &style_map,
-                highlight,
-                render,
-                &mut lines,
-                current_slide,
-            )? {
+            // The presenter view shows the control view instead of the slide.
+            let action = if let Some(broadcaster) = &broadcaster {
+                broadcaster.set_slide(current_slide, revealed);
+                self.render_control(
+                    &style,
+                    slides,
+                    current_slide,
+                    revealed,
+                    &ControlContext {
+                        events: &events,
+                        broadcaster,
+                        started,
+                    },
+                )?
+            } else {
+                Self::render_term(
+                    self,
+                    &style,
+                    highlight,
+                    render,
+                    &mut lines,
+                    &SlideState {
+                        slide,
+                        number: current_slide,
+                        total: slides.len(),
+                        revealed,
+                    },
+                    &mut PresenterState {
+                        events: &events,
+                        sender: &output_sender,
+                        pane: &mut pane,
+                        notes: &mut notes,
+                        screen,
+                    },
+                )?
+            };
+
+            // A slide entered backwards is revealed whole, the fragments are counted while rendering it.
+            let fragments = prettify::get_fragments();
+            revealed = revealed.min(fragments);
+
+            // The jump prompt and the overview move to the slide picked, or refresh the slide when they are closed.
+            let action = match action {
+                (NavigationAction::Jump, new_lines_value)
+                | (NavigationAction::Overview, new_lines_value) => {
+                    let titles: Vec<String> =
+                        slides.iter().map(|slide| slide.display_title()).collect();
+                    let picked = match action.0 {
+                        NavigationAction::Jump => {
+                            Self::jump_prompt(&titles, current_slide, &events, &mut pane)?
+                        }
+                        _ => Self::pick_slide(&titles, current_slide, &style, &events, &mut pane)?,
+                    };
+                    match picked {
+                        Some(slide) => (NavigationAction::Goto(slide, 0), new_lines_value),
+                        None => (NavigationAction::Reload, new_lines_value),
+                    }
+                }
+                action => action,
+            };
+
+            match action {
+                (NavigationAction::Next, new_lines_value) if revealed < fragments => {
+                    // The next fragment is revealed, the scroll/highlight position is kept.
+                    revealed += 1;
+                    lines = new_lines_value;
+                }
+                // Moving past the last slide ends the presentation.
+                (NavigationAction::Next, _new_lines_value)
+                    if current_slide as usize >= slides.len() =>
+                {
+                    break
+                }
                 (NavigationAction::Next, _new_lines_value) => {
                     // A fresh slide is rendered.
                     render = true;
+                    pane.dismiss();
                     current_slide += 1;
+                    revealed = 0;
                     // scrolling starts from the bottom
                     lines = 1;
                 }
+                (NavigationAction::Previous, new_lines_value) if revealed > 0 => {
+                    // The last fragment is concealed again.
+                    revealed -= 1;
+                    lines = new_lines_value;
+                }
                 (NavigationAction::Previous, _new_lines_value) => {
                     // A fresh slide is rendered.
                     render = true;
+                    pane.dismiss();
                     if current_slide > 1 {
                         current_slide -= 1;
+                        revealed = usize::MAX;
                     }
                     // scrolling starts from the bottom
                     lines = 1;
@@ -484,19 +1544,36 @@ impl Project {
                     highlight = !highlight;
                 }
                 (NavigationAction::Refresh, _new_lines_value) => {
-                    // Refreshes the slide after a change is made to the MD file.
+                    // Refreshes the slide, from the start.
                     render = true;
                     lines = 1;
                 }
-                (NavigationAction::Exit, _new_lines_value) => {
-                    // The presentation is exited.
-                    print!("{}", termion::cursor::Show);
-                    print!("{}", termion::clear::All);
-                    println!("Thank you :)");
-                    exit(0);
+                (NavigationAction::Reload, new_lines_value) => {
+                    // Refreshes the slide after a change is made to the MD file.
+                    // The scroll/highlight position is kept, and clamped to the new slide while rendering.
+                    render = false;
+                    lines = new_lines_value;
+                }
+                (NavigationAction::Exit, _new_lines_value) => break,
+                (NavigationAction::Goto(slide, fragment), _new_lines_value) => {
+                    // The slide picked is shown, or the follower moves to the slide and the fragment of the presenter view.
+                    render = true;
+                    pane.dismiss();
+                    current_slide = slide.clamp(1, slides.len() as u32);
+                    revealed = fragment;
+                    lines = 1;
                 }
-                (NavigationAction::None, _new_lines_value) => {}
+                (NavigationAction::None, _new_lines_value)
+                | (NavigationAction::Jump, _new_lines_value)
+                | (NavigationAction::Overview, _new_lines_value) => {}
             }
         }
+
+        // The presentation is exited, along with the code blocks that are still running.
+        ramen::stop_all();
+        recorder::stop()?;
+        drop(guard);
+        println!("Thank you :)");
+        Ok(())
     }
 }
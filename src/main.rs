@@ -1,14 +1,15 @@
 use clap::{App, Arg, SubCommand};
-use dough::Project;
+use dough::{Project, Recording, Screen};
 use paris::Logger;
 use std::env;
+use std::path::Path;
 use std::process;
 
 /// The main function of the program.
 ///
 /// This function initializes the paris logger, parses command-line arguments using `clap`, and
-/// dispatches commands based on the provided subcommands. It handles the 'new' and 'present'
-/// subcommands, creating or presenting projects accordingly, and prints help information
+/// dispatches commands based on the provided subcommands. It handles the 'new', 'present', 'export' and
+/// 'serve' subcommands, creating, presenting, exporting or serving projects accordingly, and prints help information
 /// if no valid subcommand is provided.
 
 fn main() {
@@ -52,7 +53,64 @@ fn main() {
                         .takes_value(true)
                         .possible_values(&["html", "term"])
                         .default_value("term")
-                        .help("Choose the mode of presentation: html or term. Currently we only support term"),
+                        .help("Choose the mode of presentation: html or term. The html mode writes a self-contained deck.html into the project directory"),
+                )
+                .arg(
+                    Arg::with_name("presenter")
+                        .long("presenter")
+                        .conflicts_with("follow")
+                        .help("Show the presenter view: the notes, the current and next slides, and the clock. Other terminals follow it with --follow"),
+                )
+                .arg(
+                    Arg::with_name("follow")
+                        .long("follow")
+                        .help("Show the slides, driven by the presenter view of the same project"),
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .long("record")
+                        .takes_value(true)
+                        .value_name("file")
+                        .help("Record the presentation in the terminal to an asciicast file, such as talk.cast, that asciinema plays back"),
+                )
+                .arg(
+                    Arg::with_name("record-code")
+                        .long("record-code")
+                        .requires("record")
+                        .help("Include the output of the code blocks run during the presentation in the recording"),
+                ),
+        )
+        .subcommand(
+            // Exports a project to a directory that can be opened offline.
+            SubCommand::with_name("export")
+                .about("Export a deck to a static HTML directory that works offline, to a PDF document, or to SVG images")
+                .arg(Arg::with_name("project-name").required(true))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["html", "pdf", "svg"])
+                        .default_value("html")
+                        .help("The format to export the deck to: an HTML directory, a PDF document with one page per slide, or a directory with an SVG image per slide"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .help("The directory to write the deck to, as an index.html page and its assets or as SVG images, or the PDF document. Defaults to <project-name>-export, <project-name>.pdf or <project-name>-svg"),
+                ),
+        )
+        .subcommand(
+            // Serves a project as a live HTML presentation on localhost.
+            SubCommand::with_name("serve")
+                .about("Serve a deck as a live HTML presentation on localhost")
+                .arg(Arg::with_name("project-name").required(true))
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .takes_value(true)
+                        .default_value("8000")
+                        .help("The port to serve the deck on. The server only listens on 127.0.0.1"),
                 ),
         )
         .get_matches();
@@ -66,6 +124,12 @@ fn main() {
     } else if let Some(args) = matches.subcommand_matches("present") {
         // Present a project
         present_project(args, &mut log);
+    } else if let Some(args) = matches.subcommand_matches("export") {
+        // Export a project
+        export_project(args, &mut log);
+    } else if let Some(args) = matches.subcommand_matches("serve") {
+        // Serve a project
+        serve_project(args, &mut log);
     } else {
         // Print help information if no valid subcommand is provided.
         print!(
@@ -136,13 +200,33 @@ fn present_project(args: &clap::ArgMatches, log: &mut Logger) {
     ));
     let cwd = env::current_dir().expect("Failed to get current working directory");
     // Create a new project instance.
-    let project = Project::new(project_name, &cwd.to_str().unwrap(), "default");
+    let project = Project::new(project_name, cwd.to_str().unwrap(), "default");
 
     match mode {
-        // The HTML mode is not implemented yet, so we only support terminal mode for now. This
-        // will be updated in the future.
+        // The HTML mode writes a self-contained HTML deck into the project directory.
+        "html" => match project.present_html() {
+            Ok(path) => {
+                log.success(format!("Deck written to {}", path.display()));
+            }
+            Err(err) => {
+                log.error(format!("Could not present project in HTML, error: {}", err));
+                process::exit(4);
+            }
+        },
         "term" | _ => {
-            if let Err(err) = project.present_term() {
+            let screen = if args.is_present("presenter") {
+                Screen::Presenter
+            } else if args.is_present("follow") {
+                Screen::Follower
+            } else {
+                Screen::Audience
+            };
+            // The recording is written relative to the current directory.
+            let recording = args.value_of("record").map(|path| Recording {
+                path: cwd.join(path),
+                code_output: args.is_present("record-code"),
+            });
+            if let Err(err) = project.present_term(screen, recording) {
                 log.error(format!(
                     "Could not present project in terminal, error: {}",
                     err
@@ -152,3 +236,85 @@ fn present_project(args: &clap::ArgMatches, log: &mut Logger) {
         }
     }
 }
+
+/// Export a project.
+/// This function exports a project to a directory, as an `index.html` page and the images it shows,
+/// to a PDF document with one page per slide, or to a directory with an SVG image per slide.
+/// If the deck cannot be exported, the function prints an error message and exits with a
+/// non-zero exit code.
+/// # Arguments
+/// * `args` - The command-line arguments provided by the user.
+/// * `log` - The paris logger instance.
+fn export_project(args: &clap::ArgMatches, log: &mut Logger) {
+    // Get the project name from the command-line arguments.
+    let project_name = args
+        .value_of("project-name")
+        .expect("project name is required");
+
+    let format = args.value_of("format").unwrap_or("html");
+
+    let cwd = env::current_dir().expect("Failed to get current working directory");
+    // The deck is exported next to the project by default, `talk` or `talk.md` to `talk-export`,
+    // `talk.pdf` or `talk-svg`.
+    let output = match args.value_of("output") {
+        Some(output) => cwd.join(output),
+        None => {
+            let name = Path::new(project_name.trim_end_matches('/'))
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "deck".to_string());
+            match format {
+                "pdf" => cwd.join(format!("{}.pdf", name)),
+                "svg" => cwd.join(format!("{}-svg", name)),
+                _ => cwd.join(format!("{}-export", name)),
+            }
+        }
+    };
+    // Create a new project instance.
+    let project = Project::new(project_name, cwd.to_str().unwrap(), "default");
+
+    let exported = match format {
+        "pdf" => project.export_pdf(&output),
+        "svg" => project.export_svg(&output),
+        _ => project.export(&output),
+    };
+    match exported {
+        Ok(path) => log.success(format!("Deck exported to {}", path.display())),
+        Err(err) => {
+            log.error(format!("Could not export project, error: {}", err));
+            process::exit(6);
+        }
+    };
+}
+
+/// Serve a project.
+/// This function serves a project as a live HTML presentation on localhost, using the provided arguments.
+/// The browser reloads whenever a slide or the style of the project changes.
+/// If the server cannot be started, the function prints an error message and exits with a
+/// non-zero exit code.
+/// # Arguments
+/// * `args` - The command-line arguments provided by the user.
+/// * `log` - The paris logger instance.
+fn serve_project(args: &clap::ArgMatches, log: &mut Logger) {
+    // Get the project name from the command-line arguments.
+    let project_name = args
+        .value_of("project-name")
+        .expect("project name is required");
+
+    let port = match args.value_of("port").unwrap_or("8000").parse::<u16>() {
+        Ok(port) => port,
+        Err(_) => {
+            log.error("The port must be a number between 0 and 65535");
+            process::exit(2);
+        }
+    };
+
+    let cwd = env::current_dir().expect("Failed to get current working directory");
+    // Create a new project instance.
+    let project = Project::new(project_name, cwd.to_str().unwrap(), "default");
+
+    if let Err(err) = project.serve(port) {
+        log.error(format!("Could not serve project, error: {}", err));
+        process::exit(5);
+    }
+}
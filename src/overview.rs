@@ -0,0 +1,254 @@
+/// The overview shows the slides of the deck as a grid of their titles, to pick a slide from.
+/// The jump prompt reads a slide number, or a part of a title, on the last row of the terminal.
+/// Both only draw, the presenter reads the keys and moves between the slides.
+use std::io::{self, Write};
+
+use colored::*;
+use termion::event::Key;
+
+use crate::keymap::{self, Action, Keymap};
+use crate::style::Style;
+use crate::utils::clip_line;
+
+/// The width of a cell of the grid, including its border.
+const CELL_WIDTH: u16 = 28;
+
+/// The height of a cell of the grid: its border, and the title.
+const CELL_HEIGHT: u16 = 3;
+
+/// Returns the number of columns of the grid in a terminal of the given width.
+pub fn columns(width: u16) -> usize {
+    (width / CELL_WIDTH).max(1) as usize
+}
+
+/// Move the selection of the grid with the keys of the previous and next slide, and of the scrolling.
+/// `Home` and `End` select the first and the last slide.
+/// # Arguments
+/// * `selected` - The index of the selected slide.
+/// * `key` - The key pressed.
+/// * `action` - The action the key is bound to in the keymap.
+/// * `total` - The number of slides.
+/// * `columns` - The number of columns of the grid.
+/// # Returns
+/// The index of the slide selected after the key, unchanged if the key does not move it.
+pub fn move_selection(
+    selected: usize,
+    key: Key,
+    action: Option<Action>,
+    total: usize,
+    columns: usize,
+) -> usize {
+    let last = total.saturating_sub(1);
+    match (key, action) {
+        (_, Some(Action::Previous)) => selected.saturating_sub(1),
+        (_, Some(Action::Next)) => (selected + 1).min(last),
+        (_, Some(Action::ScrollUp)) => selected.checked_sub(columns).unwrap_or(selected),
+        (_, Some(Action::ScrollDown)) if selected + columns <= last => selected + columns,
+        (Key::Home, _) => 0,
+        (Key::End, _) => last,
+        _ => selected,
+    }
+}
+
+/// Returns the help line of the overview, with the first keys of the actions that move the selection.
+fn help_line(keymap: &Keymap) -> String {
+    let moves: Vec<String> = [
+        Action::Previous,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::Next,
+    ]
+    .iter()
+    .filter_map(|action| keymap.keys(*action).first().map(keymap::key_name))
+    .collect();
+    let moves = if moves.is_empty() {
+        String::new()
+    } else {
+        format!(" · {} to move", moves.join("/"))
+    };
+    format!(" Overview{} · Enter to open · Esc to close", moves)
+}
+
+/// Draw the grid of the slides, with the selected slide in the colors of the highlighter.
+/// The rows of the grid scroll to keep the selected slide in view.
+/// # Arguments
+/// * `out` - The terminal to draw to.
+/// * `titles` - The titles of the slides, in order.
+/// * `selected` - The index of the selected slide.
+/// * `style` - The style of the slides.
+/// * `keymap` - The keys of the presenter, shown in the help line.
+/// * `width` - The width of the terminal.
+/// * `height` - The height of the terminal.
+pub fn draw<W: Write>(
+    out: &mut W,
+    titles: &[String],
+    selected: usize,
+    style: &Style,
+    keymap: &Keymap,
+    width: u16,
+    height: u16,
+) -> io::Result<()> {
+    let columns = columns(width);
+    // The first row holds the help line
+    let visible_rows = (height.saturating_sub(1) / CELL_HEIGHT).max(1) as usize;
+    let first_row = (selected / columns).saturating_sub(visible_rows - 1);
+    let text_width = (CELL_WIDTH - 4) as usize;
+
+    write!(out, "{}", termion::clear::All)?;
+    write!(
+        out,
+        "{}{}",
+        termion::cursor::Goto(1, 1),
+        clip_line(&help_line(keymap), width as usize, "").dimmed()
+    )?;
+
+    let shown = titles
+        .iter()
+        .enumerate()
+        .skip(first_row * columns)
+        .take(visible_rows * columns);
+    for (index, title) in shown {
+        let left = 1 + (index % columns) as u16 * CELL_WIDTH;
+        let top = 2 + (index / columns - first_row) as u16 * CELL_HEIGHT;
+        let label = clip_line(&format!("{}. {}", index + 1, title), text_width, "…");
+        let label = format!("{:<width$}", label, width = text_width);
+        let border = "─".repeat(text_width + 2);
+
+        let (label, top_border, bottom_border) = if index == selected {
+            (
+                label
+                    .color(style.highlighter.foreground)
+                    .on_color(style.highlighter.background)
+                    .to_string(),
+                format!("┌{}┐", border).bold().to_string(),
+                format!("└{}┘", border).bold().to_string(),
+            )
+        } else {
+            (
+                label,
+                format!("┌{}┐", border).dimmed().to_string(),
+                format!("└{}┘", border).dimmed().to_string(),
+            )
+        };
+        let side = if index == selected {
+            "│".bold().to_string()
+        } else {
+            "│".dimmed().to_string()
+        };
+        write!(
+            out,
+            "{}{}{}{} {} {}{}{}",
+            termion::cursor::Goto(left, top),
+            top_border,
+            termion::cursor::Goto(left, top + 1),
+            side,
+            label,
+            side,
+            termion::cursor::Goto(left, top + 2),
+            bottom_border
+        )?;
+    }
+
+    out.flush()
+}
+
+/// Draw the jump prompt on the last row of the terminal.
+/// # Arguments
+/// * `out` - The terminal to draw to.
+/// * `input` - What was typed so far.
+/// * `error` - Why the last input did not match a slide, if it did not.
+/// * `width` - The width of the terminal.
+/// * `height` - The height of the terminal.
+pub fn draw_prompt<W: Write>(
+    out: &mut W,
+    input: &str,
+    error: Option<&str>,
+    width: u16,
+    height: u16,
+) -> io::Result<()> {
+    let prompt = match error {
+        Some(error) => format!(" Go to slide: {}█ {}", input, error.red()),
+        None => format!(" Go to slide: {}█", input),
+    };
+    write!(
+        out,
+        "{}{}{}",
+        termion::cursor::Goto(1, height),
+        termion::clear::CurrentLine,
+        clip_line(&prompt, width as usize, "")
+    )?;
+    out.flush()
+}
+
+/// Find the slide the jump prompt points to.
+/// A number is the number of a slide. Any other text is searched in the titles, case insensitively,
+/// from the slide after the current one, so that the same search moves on to the next match.
+/// # Arguments
+/// * `query` - The input of the prompt.
+/// * `titles` - The titles of the slides, in order.
+/// * `current` - The index of the current slide.
+/// # Returns
+/// The index of the slide, or why no slide matches.
+pub fn find_slide(query: &str, titles: &[String], current: usize) -> Result<usize, String> {
+    let query = query.trim();
+    if let Ok(number) = query.parse::<usize>() {
+        return match number {
+            1.. if number <= titles.len() => Ok(number - 1),
+            _ => Err(format!("No slide {}", number)),
+        };
+    }
+
+    let query = query.to_lowercase();
+    (1..=titles.len())
+        .map(|offset| (current + offset) % titles.len())
+        .find(|index| titles[*index].to_lowercase().contains(&query))
+        .ok_or_else(|| format!("No slide matches \"{}\"", query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles() -> Vec<String> {
+        ["Intro", "Ownership", "Borrowing", "Lifetimes", "Outro"]
+            .iter()
+            .map(|title| title.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn help_line_shows_the_keys_of_the_keymap() {
+        let keymap = Keymap::new(&Default::default());
+        assert_eq!(
+            help_line(&keymap),
+            " Overview · h/j/k/l to move · Enter to open · Esc to close"
+        );
+
+        let bindings = serde_yaml::from_str("next: [space]\nprevious: [left]\n").unwrap();
+        assert_eq!(
+            help_line(&Keymap::new(&bindings)),
+            " Overview · left/j/k/space to move · Enter to open · Esc to close"
+        );
+    }
+
+    #[test]
+    fn find_slide_by_number() {
+        assert_eq!(find_slide("3", &titles(), 0), Ok(2));
+        assert_eq!(find_slide(" 5 ", &titles(), 0), Ok(4));
+        assert_eq!(find_slide("0", &titles(), 0), Err("No slide 0".to_string()));
+        assert_eq!(find_slide("6", &titles(), 0), Err("No slide 6".to_string()));
+    }
+
+    #[test]
+    fn find_slide_by_title_from_the_next_slide() {
+        assert_eq!(find_slide("OWN", &titles(), 0), Ok(1));
+        // The search starts after the current slide, and wraps around.
+        assert_eq!(find_slide("o", &titles(), 1), Ok(2));
+        assert_eq!(find_slide("life", &titles(), 4), Ok(3));
+        assert_eq!(find_slide("intro", &titles(), 0), Ok(0));
+        assert_eq!(
+            find_slide("traits", &titles(), 0),
+            Err("No slide matches \"traits\"".to_string())
+        );
+    }
+}
@@ -0,0 +1,328 @@
+/// The panes are drawn below the slide: the output pane shows the output of the code blocks, and
+/// the notes pane shows the speaker notes of the slide.
+/// The output is streamed into the output pane line by line while the code runs. The panes take up
+/// the bottom rows of the terminal, which are reserved while laying out the slide, so they can be
+/// redrawn on their own without touching the slide.
+use crate::ramen::{RunResult, Stream};
+use crate::utils::{strip_ansi_codes, wrap_text};
+
+use std::io::{self, Write};
+
+use colored::*;
+use unicode_width::UnicodeWidthChar;
+
+/// The smallest number of rows the pane takes, including its title.
+const MIN_HEIGHT: u16 = 4;
+
+/// A line shown in the pane.
+enum PaneLine {
+    // The start of a run, with the number of the code block.
+    Started(usize),
+    Output(Stream, String),
+    // The end of a run, with its status, and whether it succeeded.
+    Finished(String, bool),
+}
+
+#[derive(Default)]
+pub struct OutputPane {
+    lines: Vec<PaneLine>,
+    // The number of lines the pane is scrolled up from the bottom.
+    scroll: usize,
+    visible: bool,
+}
+
+impl OutputPane {
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Returns the number of rows the pane takes in a terminal of the given height.
+    /// The pane takes up a third of the terminal, and no rows when it is hidden.
+    pub fn height(&self, terminal_height: u16) -> u16 {
+        if !self.visible {
+            return 0;
+        }
+        (terminal_height / 3).max(MIN_HEIGHT).min(terminal_height)
+    }
+
+    /// Show the pane for a new run of a code block.
+    pub fn start(&mut self, block: usize) {
+        self.visible = true;
+        self.scroll = 0;
+        self.push(PaneLine::Started(block));
+    }
+
+    /// Add a line printed by a running code block.
+    pub fn output(&mut self, stream: Stream, line: &str) {
+        let line = strip_ansi_codes(line).replace('\t', "    ");
+        self.push(PaneLine::Output(stream, line));
+    }
+
+    /// Add the status of a finished code block.
+    pub fn finish(&mut self, block: usize, result: &Result<RunResult, String>) {
+        let line = match result {
+            Ok(result) => {
+                PaneLine::Finished(format!("{}: {}", block, result.status()), result.success())
+            }
+            Err(err) => PaneLine::Finished(format!("{}: {}", block, err), false),
+        };
+        self.push(line);
+    }
+
+    fn push(&mut self, line: PaneLine) {
+        // The output of a dismissed pane is dropped.
+        if !self.visible {
+            return;
+        }
+        self.lines.push(line);
+        // A scrolled pane stays on the lines it shows.
+        if self.scroll > 0 {
+            self.scroll += 1;
+        }
+    }
+
+    /// Scroll up, until the first line is at the top of the pane.
+    pub fn scroll_up(&mut self, lines: usize, terminal_height: u16) {
+        let rows = self.height(terminal_height).saturating_sub(1) as usize;
+        self.scroll = (self.scroll + lines).min(self.lines.len().saturating_sub(rows));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Hide the pane and clear its output.
+    pub fn dismiss(&mut self) {
+        self.visible = false;
+        self.lines.clear();
+        self.scroll = 0;
+    }
+
+    /// Draw the pane at the bottom of the terminal.
+    /// # Arguments
+    /// * `out` - The terminal to draw to.
+    /// * `width` - The width of the terminal.
+    /// * `height` - The height of the terminal.
+    pub fn draw<W: Write>(&self, out: &mut W, width: u16, height: u16) -> io::Result<()> {
+        let pane_height = self.height(height);
+        if pane_height == 0 {
+            return Ok(());
+        }
+        let top = height - pane_height + 1;
+        let rows = (pane_height - 1) as usize;
+
+        let title = if self.scroll > 0 {
+            format!("── output (+{}) ", self.scroll)
+        } else {
+            "── output ".to_string()
+        };
+        let hint = " PgUp/PgDn scroll · c close ──";
+        let fill = (width as usize).saturating_sub(title.chars().count() + hint.chars().count());
+        write!(
+            out,
+            "{}{}{}",
+            termion::cursor::Goto(1, top),
+            termion::clear::CurrentLine,
+            truncate(&format!("{}{}{}", title, "─".repeat(fill), hint), width).dimmed()
+        )?;
+
+        let end = self.lines.len() - self.scroll.min(self.lines.len());
+        let start = end.saturating_sub(rows);
+        for row in 0..rows {
+            write!(
+                out,
+                "{}{}",
+                termion::cursor::Goto(1, top + 1 + row as u16),
+                termion::clear::CurrentLine
+            )?;
+            let line = match self.lines.get(start + row) {
+                Some(line) if start + row < end => line,
+                _ => continue,
+            };
+            let text = match line {
+                PaneLine::Started(block) => truncate(&format!("▶ {}", block), width).cyan(),
+                PaneLine::Output(Stream::Stdout, text) => truncate(text, width).normal(),
+                PaneLine::Output(Stream::Stderr, text) => truncate(text, width).red(),
+                PaneLine::Finished(status, true) => {
+                    truncate(&format!("✔ {}", status), width).green()
+                }
+                PaneLine::Finished(status, false) => {
+                    truncate(&format!("✖ {}", status), width).red()
+                }
+            };
+            write!(out, "{}", text)?;
+        }
+        out.flush()
+    }
+}
+
+/// The notes pane, shown above the output pane.
+/// It is toggled by the presenter, and stays open from one slide to the next.
+#[derive(Default)]
+pub struct NotesPane {
+    visible: bool,
+}
+
+impl NotesPane {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Returns the number of rows the pane takes for the given notes.
+    /// The pane takes the rows the notes need, up to a third of the terminal, and no rows when it is hidden.
+    /// # Arguments
+    /// * `notes` - The notes of the slide.
+    /// * `width` - The width of the terminal.
+    /// * `terminal_height` - The rows of the terminal that are not taken by the other panes.
+    pub fn height(&self, notes: &str, width: u16, terminal_height: u16) -> u16 {
+        if !self.visible {
+            return 0;
+        }
+        let rows = notes_lines(notes, width).len() as u16 + 1;
+        rows.min((terminal_height / 3).max(MIN_HEIGHT))
+            .min(terminal_height)
+    }
+
+    /// Draw the pane, from the given row of the terminal.
+    /// # Arguments
+    /// * `out` - The terminal to draw to.
+    /// * `notes` - The notes of the slide.
+    /// * `width` - The width of the terminal.
+    /// * `top` - The first row of the pane.
+    /// * `height` - The number of rows of the pane.
+    pub fn draw<W: Write>(
+        &self,
+        out: &mut W,
+        notes: &str,
+        width: u16,
+        top: u16,
+        height: u16,
+    ) -> io::Result<()> {
+        if height == 0 {
+            return Ok(());
+        }
+        let title = "── notes ";
+        let hint = " n hide ──";
+        let fill = (width as usize).saturating_sub(title.chars().count() + hint.chars().count());
+        write!(
+            out,
+            "{}{}{}",
+            termion::cursor::Goto(1, top),
+            termion::clear::CurrentLine,
+            truncate(&format!("{}{}{}", title, "─".repeat(fill), hint), width).dimmed()
+        )?;
+
+        let lines = notes_lines(notes, width);
+        for row in 0..height - 1 {
+            write!(
+                out,
+                "{}{}",
+                termion::cursor::Goto(1, top + 1 + row),
+                termion::clear::CurrentLine
+            )?;
+            if notes.trim().is_empty() && row == 0 {
+                write!(out, "{}", "No notes for this slide".dimmed())?;
+            } else if let Some(line) = lines.get(row as usize) {
+                write!(out, "{}", truncate(line, width).yellow())?;
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Returns the lines of the notes, wrapped to the width of the terminal.
+fn notes_lines(notes: &str, width: u16) -> Vec<String> {
+    if notes.trim().is_empty() {
+        return vec![String::new()];
+    }
+    wrap_text(
+        &strip_ansi_codes(notes).replace('\t', "    "),
+        width as usize,
+        "",
+    )
+    .lines()
+    .map(|line| line.to_string())
+    .collect()
+}
+
+/// Cut a line to the width of the terminal.
+fn truncate(line: &str, width: u16) -> String {
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in line.chars() {
+        used += c.width().unwrap_or(0);
+        if used > width as usize {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a pane showing the output of a run, with the given number of lines.
+    fn pane(lines: usize) -> OutputPane {
+        let mut pane = OutputPane::default();
+        pane.start(1);
+        for i in 0..lines {
+            pane.output(Stream::Stdout, &format!("line {}", i));
+        }
+        pane
+    }
+
+    /// Returns the text drawn by the pane, without the escape codes.
+    fn drawn(pane: &OutputPane, width: u16, height: u16) -> String {
+        let mut out = Vec::new();
+        pane.draw(&mut out, width, height).unwrap();
+        strip_ansi_codes(&String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn output_pane_takes_a_third_of_the_terminal() {
+        assert_eq!(OutputPane::default().height(30), 0);
+        assert_eq!(pane(0).height(30), 10);
+        assert_eq!(pane(0).height(9), MIN_HEIGHT);
+        assert_eq!(pane(0).height(2), 2);
+    }
+
+    #[test]
+    fn output_pane_scrolls_within_its_output() {
+        // The pane of a 12-row terminal shows 3 lines under its title, out of the 11 lines of the run.
+        let mut pane = pane(10);
+        pane.scroll_up(100, 12);
+        assert_eq!(pane.scroll, 8);
+        assert!(drawn(&pane, 40, 12).contains("output (+8)"));
+        assert!(drawn(&pane, 40, 12).contains("line 1"));
+
+        // New output keeps a scrolled pane on the lines it shows.
+        pane.output(Stream::Stderr, "late");
+        assert_eq!(pane.scroll, 9);
+        pane.scroll_down(100);
+        assert_eq!(pane.scroll, 0);
+        let text = drawn(&pane, 40, 12);
+        assert!(text.contains("late") && text.contains("line 9"));
+        assert!(!text.contains("line 7"));
+    }
+
+    #[test]
+    fn dismissed_pane_drops_its_output() {
+        let mut pane = pane(3);
+        pane.dismiss();
+        pane.output(Stream::Stdout, "after");
+        assert!(!pane.is_visible());
+        assert!(pane.lines.is_empty());
+        assert_eq!(drawn(&pane, 40, 12), "");
+    }
+
+    #[test]
+    fn notes_pane_takes_the_rows_of_the_notes() {
+        let mut notes = NotesPane::default();
+        assert_eq!(notes.height("one\ntwo", 40, 30), 0);
+        notes.toggle();
+        assert_eq!(notes.height("one\ntwo", 40, 30), 3);
+        assert_eq!(notes.height(&"word ".repeat(200), 40, 30), 10);
+    }
+}
@@ -0,0 +1,388 @@
+/// The PDF backend writes a deck as a PDF document, one page per slide.
+/// Every page draws the grid of its slide, as it is laid out for the terminal, with the standard
+/// Courier fonts of PDF readers, so that the document needs no embedded fonts. Box-drawing
+/// characters, block elements and checkboxes are drawn as shapes, since those fonts do not have them.
+use crate::grid::{Attributes, Canvas, Grid, Rgb, DEFAULT_BACKGROUND};
+
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+/// The size of the text, in points. A character of Courier is 0.6 times as wide as its size.
+const FONT_SIZE: f32 = 10.0;
+/// The size of a cell of the grid, in points.
+const CELL_WIDTH: f32 = 6.0;
+const CELL_HEIGHT: f32 = 12.0;
+/// The space around the slide, in points.
+const MARGIN: f32 = 18.0;
+/// The height of the baseline of the text above the bottom of its cell, in points.
+const BASELINE: f32 = 3.0;
+
+/// The objects that come before the pages: the catalog, the page tree, the four fonts and the document info.
+const FIRST_PAGE_OBJECT: usize = 8;
+
+/// The fonts of the document, by their resource name, regular, bold, oblique and bold oblique.
+const FONTS: [(&str, &str); 4] = [
+    ("F1", "Courier"),
+    ("F2", "Courier-Bold"),
+    ("F3", "Courier-Oblique"),
+    ("F4", "Courier-BoldOblique"),
+];
+
+/// Write a deck as a PDF document.
+/// # Arguments
+/// * `title` - The title of the document.
+/// * `slides` - The grids of the slides, one page each.
+/// # Returns
+/// The bytes of the document, and the characters the fonts have no glyph for, which are written as `?`.
+pub fn write_deck(title: &str, slides: &[Grid]) -> (Vec<u8>, BTreeSet<char>) {
+    let mut document = Document::new();
+    let mut missing = BTreeSet::new();
+
+    let kids: Vec<String> = (0..slides.len())
+        .map(|i| format!("{} 0 R", FIRST_PAGE_OBJECT + 2 * i))
+        .collect();
+    document.object(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    document.object(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            slides.len()
+        )
+        .into_bytes(),
+    );
+    for (_, font) in FONTS {
+        document.object(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .into_bytes(),
+        );
+    }
+    document.object(format!("<< /Title {} /Producer (dough) >>", text_string(title)).into_bytes());
+
+    let fonts: Vec<String> = FONTS
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| format!("/{} {} 0 R", name, 3 + i))
+        .collect();
+    for (i, grid) in slides.iter().enumerate() {
+        let width = grid.cols as f32 * CELL_WIDTH + 2.0 * MARGIN;
+        let height = grid.rows as f32 * CELL_HEIGHT + 2.0 * MARGIN;
+        document.object(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+                number(width),
+                number(height),
+                fonts.join(" "),
+                FIRST_PAGE_OBJECT + 2 * i + 1
+            )
+            .into_bytes(),
+        );
+        document.stream(&draw_page(grid, width, height, &mut missing));
+    }
+
+    (document.finish(), missing)
+}
+
+/// A PDF document being written, with the offsets of its objects for the cross-reference table.
+struct Document {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Document {
+    fn new() -> Document {
+        // The comment with bytes above 127 tells the programs reading the file that it is binary.
+        Document {
+            bytes: b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Add the next object, numbered from 1.
+    fn object(&mut self, body: Vec<u8>) {
+        self.offsets.push(self.bytes.len());
+        self.bytes
+            .extend(format!("{} 0 obj\n", self.offsets.len()).into_bytes());
+        self.bytes.extend(body);
+        self.bytes.extend(b"\nendobj\n");
+    }
+
+    /// Add the next object as a compressed stream.
+    fn stream(&mut self, data: &[u8]) {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        // Writing to a vector does not fail.
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut body = format!(
+            "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+            compressed.len()
+        )
+        .into_bytes();
+        body.extend(compressed);
+        body.extend(b"\nendstream");
+        self.object(body);
+    }
+
+    /// Add the cross-reference table and the trailer, and return the bytes of the document.
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.bytes.len();
+        let count = self.offsets.len() + 1;
+        self.bytes
+            .extend(format!("xref\n0 {}\n0000000000 65535 f \n", count).into_bytes());
+        for offset in &self.offsets {
+            self.bytes
+                .extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        self.bytes.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info 7 0 R >>\nstartxref\n{}\n%%EOF\n",
+                count, xref
+            )
+            .into_bytes(),
+        );
+        self.bytes
+    }
+}
+
+/// Draw the grid of a slide on a page, with the background of the terminal around it.
+/// The characters the fonts have no glyph for are added to `missing`.
+fn draw_page(grid: &Grid, width: f32, height: f32, missing: &mut BTreeSet<char>) -> Vec<u8> {
+    let mut page = Page {
+        content: Vec::new(),
+        top: height - MARGIN,
+        missing,
+    };
+    page.fill(DEFAULT_BACKGROUND, -MARGIN, -MARGIN, width, height);
+    grid.draw(&mut page, CELL_WIDTH, CELL_HEIGHT);
+    page.content
+}
+
+/// The content of a page. PDF positions are from the bottom left corner of the page, those of
+/// the grid from its top left corner, within the margins.
+struct Page<'a> {
+    content: Vec<u8>,
+    top: f32,
+    // The characters written as `?`, since the fonts have no glyph for them.
+    missing: &'a mut BTreeSet<char>,
+}
+
+impl Page<'_> {
+    /// Returns the position of a point of the grid on the page.
+    fn point(&self, x: f32, y: f32) -> String {
+        format!("{} {}", number(MARGIN + x), number(self.top - y))
+    }
+}
+
+impl Canvas for Page<'_> {
+    fn fill(&mut self, rgb: Rgb, x: f32, y: f32, width: f32, height: f32) {
+        self.content.extend(
+            format!(
+                "{} rg {} {} {} re f\n",
+                color(rgb),
+                self.point(x, y + height),
+                number(width),
+                number(height)
+            )
+            .into_bytes(),
+        );
+    }
+
+    fn text(&mut self, text: &str, attributes: &Attributes, x: f32, y: f32, _width: f32) {
+        let (foreground, _) = attributes.colors();
+        let font = match (attributes.bold, attributes.italic) {
+            (false, false) => "F1",
+            (true, false) => "F2",
+            (false, true) => "F3",
+            (true, true) => "F4",
+        };
+        self.content.extend(
+            format!(
+                "BT /{} {} Tf {} rg {} Td (",
+                font,
+                number(FONT_SIZE),
+                color(foreground),
+                self.point(x, y + CELL_HEIGHT - BASELINE)
+            )
+            .into_bytes(),
+        );
+        for c in text.chars() {
+            let byte = win_ansi(c).unwrap_or_else(|| {
+                self.missing.insert(c);
+                b'?'
+            });
+            match byte {
+                b'(' | b')' | b'\\' => self.content.extend([b'\\', byte]),
+                0x80..=0xff => self.content.extend(format!("\\{:03o}", byte).into_bytes()),
+                _ => self.content.push(byte),
+            }
+        }
+        self.content.extend(b") Tj ET\n");
+    }
+
+    fn checkbox(&mut self, rgb: Rgb, x: f32, y: f32, size: f32, checked: bool) {
+        self.content.extend(
+            format!(
+                "{} RG 0.6 w {} {} {} re S\n",
+                color(rgb),
+                self.point(x, y + size),
+                number(size),
+                number(size)
+            )
+            .into_bytes(),
+        );
+        if checked {
+            self.content.extend(
+                format!(
+                    "{} m {} l {} l S\n",
+                    self.point(x + size * 0.2, y + size * 0.5),
+                    self.point(x + size * 0.4, y + size * 0.8),
+                    self.point(x + size * 0.85, y + size * 0.15)
+                )
+                .into_bytes(),
+            );
+        }
+    }
+}
+
+/// Returns a color as the operands of the PDF color operators.
+fn color(rgb: Rgb) -> String {
+    let component = |value: u8| number(value as f32 / 255.0);
+    format!(
+        "{} {} {}",
+        component(rgb.0),
+        component(rgb.1),
+        component(rgb.2)
+    )
+}
+
+/// Returns a number as it is written in PDF, with at most three decimals.
+fn number(value: f32) -> String {
+    let value = format!("{:.3}", value);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    match value {
+        "" | "-0" => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Returns the byte of a character in the WinAnsi encoding of the standard fonts, or None if it has none.
+fn win_ansi(c: char) -> Option<u8> {
+    let byte = match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8a,
+        '‹' => 0x8b,
+        'Œ' => 0x8c,
+        'Ž' => 0x8e,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9a,
+        '›' => 0x9b,
+        'œ' => 0x9c,
+        'ž' => 0x9e,
+        'Ÿ' => 0x9f,
+        _ => return None,
+    };
+    Some(byte)
+}
+
+/// Returns a string for the document info, in UTF-16 so that any title can be written.
+fn text_string(text: &str) -> String {
+    let mut string = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        string.push_str(&format!("{:04X}", unit));
+    }
+    string.push('>');
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_page_per_slide() {
+        let slides = [Grid::parse("One", 10, 2), Grid::parse("Two", 10, 2)];
+        let (document, missing) = write_deck("Talk", &slides);
+        let text = String::from_utf8_lossy(&document);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.contains("/Kids [8 0 R 10 0 R] /Count 2"));
+        assert!(text.contains("/MediaBox [0 0 96 60]"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn cross_reference_offsets() {
+        let (document, _) = write_deck("Talk", &[Grid::parse("One", 10, 2)]);
+        let text = String::from_utf8_lossy(&document);
+        let xref = &text[text.find("xref\n").unwrap()..];
+        for (i, line) in xref.lines().skip(3).take(9).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            let object = format!("{} 0 obj\n", i + 1);
+            assert!(
+                document[offset..].starts_with(object.as_bytes()),
+                "{}",
+                object
+            );
+        }
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let mut missing = BTreeSet::new();
+        let mut page = Page {
+            content: Vec::new(),
+            top: 100.0,
+            missing: &mut missing,
+        };
+        page.text("(a) \\ é€▶", &Attributes::default(), 0.0, 0.0, 60.0);
+        let content = String::from_utf8(page.content).unwrap();
+        assert!(
+            content.ends_with("(\\(a\\) \\\\ \\351\\200?) Tj ET\n"),
+            "{}",
+            content
+        );
+        assert_eq!(missing.into_iter().collect::<Vec<char>>(), vec!['▶']);
+    }
+
+    #[test]
+    fn missing_glyphs_are_reported() {
+        let (_, missing) = write_deck("Talk", &[Grid::parse("▶ 日本 café", 20, 1)]);
+        assert_eq!(missing.into_iter().collect::<String>(), "▶日本");
+    }
+
+    #[test]
+    fn title_in_utf16() {
+        assert_eq!(text_string("Té"), "<FEFF005400E9>");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(12.0), "12");
+        assert_eq!(number(0.1234), "0.123");
+        assert_eq!(number(-0.0001), "0");
+    }
+}
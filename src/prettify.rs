@@ -1,10 +1,13 @@
 extern crate lazy_static;
+use crate::graphics;
+use crate::style::Style;
 use crate::utils::{
     calculate_length_of_line, calculate_length_of_longest_line, check_if_text_is_right_aligned,
-    store_colors, strip_ansi_codes,
+    clip_line, conceal, store_colors, strip_ansi_codes, visible_width, wrap_text, PAUSE,
 };
 
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::{collections::HashMap, str};
 
@@ -13,7 +16,7 @@ use markdown::mdast::{self};
 use regex::Regex;
 
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style, ThemeSet};
+use syntect::highlighting::{Style as SyntectStyle, ThemeSet};
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 
@@ -21,14 +24,37 @@ use unicode_width::UnicodeWidthStr;
 
 use lazy_static::lazy_static;
 
+/// The marker at the end of a line of code that is too wide for the slide
+const CLIP_MARKER: &str = "…";
+
+/// The narrowest the content of a slide gets, however small the terminal is
+const MIN_CONTENT_WIDTH: usize = 20;
+
 lazy_static! {
-    /// Style map is used to store the styles associated with a particular markdown element
-    /// The styles are stored as a HashMap with the key being the name of the markdown element
-    /// and the value being the style associated with it.
-    /// The styles are stored as strings and are converted to the appropriate type when needed.
-    /// The styles are stored in the global STYLES variable, which is a Mutex<HashMap<String, String>>
-    /// This also stores the upper and lower bounds of the content, which is used for vertical alignment
-    static ref STYLES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    /// The style of the slide being rendered, read from the style.yml file of the project
+    /// The styles are stored in the global STYLES variable, which is a Mutex<Style>
+    static ref STYLES: Mutex<Style> = Mutex::new(Style::default());
+
+    /// The upper and lower bounds of the content, which are used for vertical alignment and scrolling
+    static ref BOUNDS: Mutex<(u32, u32)> = Mutex::new((0, 0));
+
+    /// The rows at the bottom of the terminal that are kept free of the slide, such as the rows of the output pane
+    static ref RESERVED_ROWS: Mutex<u16> = Mutex::new(0);
+
+    /// The size the slides are laid out for, instead of the size of the terminal, such as the previews of the presenter view
+    static ref VIEWPORT: Mutex<Option<(u16, u16)>> = Mutex::new(None);
+
+    /// The project directory, which the paths of the images are relative to
+    static ref BASE_DIR: Mutex<PathBuf> = Mutex::new(PathBuf::new());
+
+    /// The number of fragments of the slide that are revealed, and whether its lists are incremental
+    static ref REVEAL: Mutex<(usize, bool)> = Mutex::new((usize::MAX, false));
+
+    /// The source offset from which the blocks of the slide being rendered are concealed, if any
+    static ref CONCEALED_FROM: Mutex<Option<usize>> = Mutex::new(None);
+
+    /// The number of fragments of the last slide rendered, after the part shown up front
+    static ref FRAGMENTS: Mutex<usize> = Mutex::new(0);
 
 
     /// This is used to store the colors associated with each line of the content
@@ -73,14 +99,24 @@ fn join_children(children: Vec<mdast::Node>, depth: usize) -> String {
 /// The string is decorated with the appropriate styles
 /// The styles are fetched from the global STYLES variable
 fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
-    let style_map = STYLES.lock().unwrap();
-    let styles = style_map.clone();
-    drop(style_map);
+    let global_styles = STYLES.lock().unwrap();
+    let styles = global_styles.clone();
+    drop(global_styles);
 
     match node {
         mdast::Node::Root(root) => {
             let mut result = String::default();
-            result.push_str(&join_children(root.children, depth));
+            // The blocks that are not revealed yet keep their place in the slide
+            for child in root.children {
+                let concealed = is_concealed(&child);
+                if let Some(text) = visit_md_node(child, depth) {
+                    if concealed {
+                        result.push_str(&conceal(&text));
+                    } else {
+                        result.push_str(&text);
+                    }
+                }
+            }
             result.push('\n');
             Some(result)
         }
@@ -102,15 +138,19 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
                         .collect::<String>();
                     let text_to_replace = format!("~~{}~~", matched_text);
                     let replaced_text = text_start.replace(&text_to_replace, &strikethrough_text);
-                    result.push_str(&replaced_text);
+                    result.push_str(&wrap_text(&replaced_text, content_width(&styles), ""));
                 }
             } else {
                 // the depth is used to calculate the indentation
                 // Used in nested lists/ blockquotes
+                // The continuation lines of a wrapped paragraph are indented the same way
 
                 let item_text = " ".white().on_black().to_string().repeat(depth);
-                result.push_str(&item_text);
-                result.push_str(text_start);
+                result.push_str(&wrap_text(
+                    &format!("{}{}", item_text, text_start),
+                    content_width(&styles),
+                    &item_text,
+                ));
             }
 
             result.push('\n');
@@ -143,64 +183,64 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
             let level = heading.depth;
             let mut result = String::from("\n");
 
-            let color: &str;
+            let color: crate::style::Color;
             let mut item_text = String::new();
 
             match level {
                 1 => {
-                    color = styles.get("h1").map(|s| s.as_str()).unwrap_or("red");
+                    color = styles.h1;
                     item_text.push_str(
                         &format!("█ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 1, &styles));
                 }
                 2 => {
-                    color = styles.get("h2").map(|s| s.as_str()).unwrap_or("yellow");
+                    color = styles.h2;
                     item_text.push_str(
                         &format!("██ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 2, &styles));
                 }
                 3 => {
-                    color = styles.get("h3").map(|s| s.as_str()).unwrap_or("green");
+                    color = styles.h3;
                     item_text.push_str(
                         &format!("███ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 3, &styles));
                 }
                 4 => {
-                    color = styles.get("h4").map(|s| s.as_str()).unwrap_or("blue");
+                    color = styles.h4;
                     item_text.push_str(
                         &format!("████ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 4, &styles));
                 }
                 5 => {
-                    color = styles.get("h5").map(|s| s.as_str()).unwrap_or("magenta");
+                    color = styles.h5;
                     item_text.push_str(
                         &format!("█████ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 5, &styles));
                 }
 
                 6 => {
-                    color = styles.get("h6").map(|s| s.as_str()).unwrap_or("cyan");
+                    color = styles.h6;
                     item_text.push_str(
                         &format!("██████ {}", join_children(heading.children, depth))
                             .color(color)
                             .to_string(),
                     );
-                    result.push_str(&item_text);
+                    result.push_str(&wrap_heading(&item_text, 6, &styles));
                 }
                 _ => result.push_str(&join_children(heading.children, depth)),
             }
@@ -213,10 +253,7 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
 
             let mut result = String::from("`").replace("`", "");
 
-            let color: &str = styles
-                .get("inline_code")
-                .map(|s| s.as_str())
-                .unwrap_or("red");
+            let color = styles.inline_code;
 
             result.push_str("\x1b[0m");
             result.push_str(&text.color(color).to_string());
@@ -238,27 +275,12 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
             codes.insert(last_index + 1, (language.clone(), code.value.to_string()));
             drop(codes);
 
-            let syntax_theme = styles
-                .get("syntax_theme")
-                .map(|s| s.as_str())
-                .unwrap_or("base16-ocean.dark")
-                .to_string();
-            let syntax_highlighting = styles
-                .get("syntax_highlighting")
-                .map(|s| s.as_str())
-                .unwrap_or("true");
-
-            let include_background_color: bool = match styles
-                .get("syntax_bg")
-                .map(|s| s.as_str())
-                .unwrap_or("false")
-            {
-                "true" | "True" => true,
-                _ => false,
-            };
+            let syntax_theme = styles.syntax_theme.clone();
+            let syntax_highlighting = styles.syntax_highlighting;
+            let include_background_color = styles.syntax_bg;
 
             let mut result = String::from("```\n").replace("```", "");
-            if syntax_highlighting == "true" {
+            if syntax_highlighting {
                 let mut highlighted_code = syntax_highlighter(
                     &language,
                     code.value.to_string(),
@@ -266,16 +288,23 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
                     include_background_color,
                 );
 
+                // Lines that are too wide are clipped, wrapping them would break the code
                 highlighted_code = highlighted_code
                     .lines()
-                    .map(|line| format!("{}", line))
+                    .map(|line| clip_line(line, content_width(&styles), CLIP_MARKER))
                     .collect::<Vec<String>>()
                     .join("\n");
                 result.push_str(&highlighted_code.to_string());
             } else {
                 // A tab is replaced by 4 spaces to ensure uniform indentation across different terminals and different widths
-                let escaped = code.value.replace("\t", "    ");
-                result.push_str(&&escaped.to_string());
+                let escaped = code
+                    .value
+                    .replace("\t", "    ")
+                    .lines()
+                    .map(|line| clip_line(line, content_width(&styles), CLIP_MARKER))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                result.push_str(&escaped);
             }
             result.push_str("\n```\n".replace("```", "").as_str());
             Some(result)
@@ -294,23 +323,21 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
         )),
 
         mdast::Node::Link(link) => {
-            let color_url = styles
-                .get("link_url")
-                .map(|s| s.as_str())
-                .unwrap_or("green");
-            let color_text = styles
-                .get("link_text")
-                .map(|s| s.as_str())
-                .unwrap_or("blue");
+            let color_url = styles.link_url;
+            let color_text = styles.link_text;
 
             let mut result = String::from("[");
             result = result.replace("[", "");
 
-            result.push_str(
-                &join_children(link.children, depth)
-                    .color(color_text)
-                    .to_string(),
-            );
+            let text = join_children(link.children, depth);
+
+            // Bare URLs are links to themselves, the URL is only rendered once
+            if strip_ansi_codes(&text) == link.url {
+                result.push_str(&link.url.color(color_url).to_string());
+                return Some(result);
+            }
+
+            result.push_str(&text.color(color_text).to_string());
 
             result.push_str(" - ");
             result.push_str(&link.url.color(color_url).to_string());
@@ -321,16 +348,8 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
         mdast::Node::ThematicBreak(_) => Some("\n---\n".to_string()),
 
         mdast::Node::BlockQuote(blockquote) => {
-            let default_blockquote_color = "black on white".to_string();
-
-            let color = styles
-                .get("blockquote")
-                .map(|s| s.as_str())
-                .unwrap_or(&default_blockquote_color);
-
-            let colors: Vec<&str> = color.split(" on ").collect();
-            let foreground_color = colors[0];
-            let background_color = colors[1];
+            let foreground_color = styles.blockquote.foreground;
+            let background_color = styles.blockquote.background;
 
             let mut result = String::default();
             result.push_str(
@@ -345,26 +364,14 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
         }
 
         mdast::Node::List(list) => {
-            let bullet_color: &str = match list.ordered {
-                true => styles
-                    .get("ordered_list_bullet")
-                    .map(|s| s.as_str())
-                    .unwrap_or("green"),
-                false => styles
-                    .get("unordered_list_bullet")
-                    .map(|s| s.as_str())
-                    .unwrap_or("green"),
+            let bullet_color = match list.ordered {
+                true => styles.ordered_list_bullet,
+                false => styles.unordered_list_bullet,
             };
 
-            let text_color: &str = match list.ordered {
-                true => styles
-                    .get("ordered_list")
-                    .map(|s| s.as_str())
-                    .unwrap_or("blue"),
-                false => styles
-                    .get("unordered_list")
-                    .map(|s| s.as_str())
-                    .unwrap_or("blue"),
+            let text_color = match list.ordered {
+                true => styles.ordered_list,
+                false => styles.unordered_list,
             };
 
             let mut result = String::default();
@@ -392,10 +399,20 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
                     item_text.push_str(sep.to_string().color(bullet_color).to_string().as_str());
                 }
 
+                let concealed = is_concealed(&item);
                 if let mdast::Node::ListItem(list_item) = item {
+                    // Task list items are rendered with a checkbox
+                    match list_item.checked {
+                        Some(true) => item_text.push_str("☑ "),
+                        Some(false) => item_text.push_str("☐ "),
+                        None => {}
+                    }
+                    // The continuation lines of a wrapped item start under its text
+                    let indent = " ".repeat(visible_width(&item_text));
                     for child in list_item.children {
                         if let mdast::Node::Paragraph(paragraph) = child {
                             item_text.push_str(&join_children(paragraph.children, depth + 1));
+                            item_text = wrap_text(&item_text, content_width(&styles), &indent);
                         } else {
                             item_text.push_str(&join_children(vec![child], depth + 1));
                         }
@@ -403,6 +420,9 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
                 }
 
                 item_text.push('\n');
+                if concealed {
+                    item_text = conceal(&item_text);
+                }
                 result.push_str(&item_text.color(text_color).to_string());
                 item_number += 1;
             }
@@ -420,11 +440,55 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
             delete.children,
         )),
 
+        mdast::Node::Image(image) => {
+            // The image may take the width of the content, and half of the height of the terminal
+            let (_, height) = viewport_size();
+            let height = height.saturating_sub(*RESERVED_ROWS.lock().unwrap());
+            let max_cols = content_width(&styles) as u32;
+            let max_rows = (height as u32 / 2).max(1);
+
+            let path = BASE_DIR.lock().unwrap().join(&image.url);
+            match graphics::placeholder(&path, max_cols, max_rows) {
+                Ok(lines) => Some(lines),
+                // An image that cannot be drawn is replaced by its alt text
+                Err(err) => Some(
+                    format!("[{}] ({}: {})", image.alt, image.url, err)
+                        .color(styles.link_text)
+                        .to_string(),
+                ),
+            }
+        }
+
+        mdast::Node::Table(table) => {
+            Some(draw_table(table, depth, &styles, content_width(&styles)))
+        }
+
+        mdast::Node::FootnoteReference(reference) => Some(
+            format!("[^{}]", reference.identifier)
+                .color(styles.definition)
+                .to_string(),
+        ),
+
+        mdast::Node::FootnoteDefinition(footnote) => {
+            let mut result = String::from("\n");
+            result.push_str(
+                &format!("[^{}]: ", footnote.identifier)
+                    .color(styles.definition)
+                    .to_string(),
+            );
+            for child in footnote.children {
+                if let mdast::Node::Paragraph(paragraph) = child {
+                    result.push_str(&join_children(paragraph.children, depth));
+                } else {
+                    result.push_str(&join_children(vec![child], depth));
+                }
+            }
+            result.push('\n');
+            Some(result)
+        }
+
         mdast::Node::Definition(definition) => {
-            let color = styles
-                .get("definition")
-                .map(|s| s.as_str())
-                .unwrap_or("green");
+            let color = styles.definition;
 
             let mut result = String::from("[");
             result.push_str(&definition.identifier.color(color).to_string());
@@ -439,6 +503,145 @@ fn visit_md_node(node: mdast::Node, depth: usize) -> Option<String> {
     }
 }
 
+/// This function is used to get the number of columns the content of a slide may take
+/// The content takes the width of the terminal, within the box, up to the max_width set in the style map
+fn content_width(styles: &Style) -> usize {
+    let (width, _) = viewport_size();
+    let mut width = width as usize;
+    if styles.boxed {
+        // The sides of the box, and the spaces inside them
+        width = width.saturating_sub(6);
+    }
+    if styles.max_width > 0 {
+        width = width.min(styles.max_width);
+    }
+    width.max(MIN_CONTENT_WIDTH)
+}
+
+/// This function is used to wrap a heading, the continuation lines start under the text of the heading
+fn wrap_heading(heading: &str, level: usize, styles: &Style) -> String {
+    wrap_text(heading, content_width(styles), &" ".repeat(level + 1))
+}
+
+/// The narrowest a column of a table is shrunk to, so that a wide character still fits on a line
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// This function is used to draw a table with box-drawing borders
+/// The columns are as wide as their widest cell, and the cells are aligned according to the alignment of their column
+/// A table wider than the slide has its widest columns shrunk, and their cells wrapped over several lines
+/// The lines of a table that still does not fit are clipped, like the lines of code
+/// The header row is colored with the table_header color of the style map
+fn draw_table(table: mdast::Table, depth: usize, styles: &Style, max_width: usize) -> String {
+    let rows: Vec<Vec<String>> = table
+        .children
+        .into_iter()
+        .filter_map(|row| match row {
+            mdast::Node::TableRow(row) => Some(
+                row.children
+                    .into_iter()
+                    .map(|cell| match cell {
+                        mdast::Node::TableCell(cell) => join_children(cell.children, depth),
+                        cell => join_children(vec![cell], depth),
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect();
+
+    let columns = rows
+        .iter()
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0)
+        .max(table.align.len());
+    let mut widths = vec![0; columns];
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(visible_width(cell));
+        }
+    }
+
+    // Every column takes its padding and a border
+    let room = max_width.saturating_sub(3 * columns + 1);
+    let floors: Vec<usize> = widths.iter().map(|w| (*w).min(MIN_COLUMN_WIDTH)).collect();
+    while widths.iter().sum::<usize>() > room {
+        let widest = (0..columns).max_by_key(|i| widths[*i]).unwrap_or(0);
+        if widths[widest] <= floors[widest] {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let line = widths
+            .iter()
+            .map(|width| "─".repeat(width + 2))
+            .collect::<Vec<String>>()
+            .join(middle);
+        format!("{}{}{}", left, line, right)
+            .color(styles.table_border)
+            .to_string()
+    };
+    let separator = "│".color(styles.table_border).to_string();
+
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for (i, row) in rows.iter().enumerate() {
+        // The cells are wrapped to the width of their column, the row is as high as its highest cell
+        let cells: Vec<Vec<String>> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| {
+                let cell = row.get(column).map(String::as_str).unwrap_or("");
+                wrap_text(cell, *width, "")
+                    .split('\n')
+                    .map(String::from)
+                    .collect()
+            })
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        for line in 0..height {
+            let mut text_line = separator.clone();
+            for (column, width) in widths.iter().enumerate() {
+                let cell = cells[column].get(line).map(String::as_str).unwrap_or("");
+                let padding = width.saturating_sub(visible_width(cell));
+                let (left, right) = match table.align.get(column) {
+                    Some(mdast::AlignKind::Right) => (padding, 0),
+                    Some(mdast::AlignKind::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                let text = if i == 0 {
+                    cell.color(styles.table_header).bold().to_string()
+                } else {
+                    cell.to_string()
+                };
+                text_line.push_str(&format!(
+                    " {}{}{} {}",
+                    " ".repeat(left),
+                    text,
+                    " ".repeat(right),
+                    separator
+                ));
+            }
+            lines.push(text_line);
+        }
+
+        // The header is separated from the body
+        if i == 0 && rows.len() > 1 {
+            lines.push(border("├", "┼", "┤"));
+        }
+    }
+    lines.push(border("└", "┴", "┘"));
+
+    let mut result = String::from("\n");
+    for line in lines {
+        result.push_str(&clip_line(&line, max_width, CLIP_MARKER));
+        result.push('\n');
+    }
+    result
+}
+
 /// This function is used to draw a margin around the content based on the flag set in the style map
 /// The flag is set to true by default
 
@@ -493,14 +696,14 @@ pub fn draw_box(content: &str, line_color_map: &HashMap<usize, String>) -> Strin
 /// The flag is set to true by default
 pub fn align_vertical(
     mut prettified: String,
-    style_map: &HashMap<String, String>,
+    style: &Style,
     height: u16,
     upper_bound: &mut u32,
     lower_bound: &mut u32,
 ) -> String {
     let blank_lines;
 
-    if style_map.get("vertical_alignment").unwrap() == "false" {
+    if !style.vertical_alignment {
         blank_lines = 0;
     } else {
         if height > prettified.lines().count() as u16 {
@@ -535,7 +738,7 @@ pub fn align_vertical(
 ///
 pub fn align_horizontal(
     prettified: String,
-    style_map: &HashMap<String, String>,
+    style: &Style,
     width: u16,
     line_color_map: HashMap<usize, String>,
     right_alignment: bool,
@@ -544,7 +747,7 @@ pub fn align_horizontal(
     let spaces = if right_alignment { false } else { true };
     let longest_line = calculate_length_of_longest_line(&prettified, spaces);
 
-    if style_map.get("horizontal_alignment").unwrap() == "false" {
+    if !style.horizontal_alignment {
         blank_chars = 0;
     } else {
         if width > longest_line as u16 {
@@ -587,11 +790,7 @@ pub fn align_horizontal(
 /// $[clr]$ -> center, left, right alignment respectively
 /// This is used for text alignment within the content
 
-pub fn align_custom(
-    mut prettified: String,
-    highlight_line_num: u32,
-    style_map: &HashMap<String, String>,
-) -> String {
+pub fn align_custom(mut prettified: String, highlight_line_num: u32, style: &Style) -> String {
     // calculate the length of the longest line
     let longest_line = calculate_length_of_longest_line(&prettified, true);
 
@@ -610,14 +809,8 @@ pub fn align_custom(
     }
 
     if highlight_line_num > 0 {
-        let default_highlight_color = "black on white".to_string();
-        let highlight_color = style_map
-            .get("highlighter")
-            .unwrap_or(&default_highlight_color);
-
-        let colors: Vec<&str> = highlight_color.split(" on ").collect();
-        let foreground_color = colors[0];
-        let background_color = colors[1];
+        let foreground_color = style.highlighter.foreground;
+        let background_color = style.highlighter.background;
 
         prettified = content_lines.join("\n");
 
@@ -737,12 +930,10 @@ pub fn align_custom(
 /// 3. vertical_alignment: true/false
 /// 4. terminal: warp/normal    
 
-pub fn align_content(
-    mut prettified: String,
-    style_map: &HashMap<String, String>,
-    highlight_line_num: u32,
-) -> String {
-    let (_width, height) = termion::terminal_size().unwrap();
+pub fn align_content(mut prettified: String, style: &Style, highlight_line_num: u32) -> String {
+    let (_width, height) = viewport_size();
+    // The slide is laid out in the rows that are not reserved
+    let height = height.saturating_sub(*RESERVED_ROWS.lock().unwrap());
 
     // Bounds are used for scrolling
     let mut upper_bound = prettified.lines().count() as u32;
@@ -750,10 +941,10 @@ pub fn align_content(
     let right_aligned = check_if_text_is_right_aligned(&prettified.clone());
 
     // Custom text alignment, including highlighting
-    prettified = align_custom(prettified, highlight_line_num, style_map);
+    prettified = align_custom(prettified, highlight_line_num, style);
 
     // draw a margin around the content based on the flag set in the style map
-    if style_map.get("box").unwrap() == "true" {
+    if style.boxed {
         // A HashMap is used to store the colors for each line
         let content_lines: Vec<String> = prettified.lines().map(|s| s.to_string()).collect();
         let line_color_map = store_colors(&content_lines);
@@ -762,18 +953,18 @@ pub fn align_content(
     }
 
     // align the content horizontally based on the flag set in the style map
-    if style_map.get("horizontal_alignment").unwrap() == "true" {
+    if style.horizontal_alignment {
         let content_lines: Vec<String> = prettified.lines().map(|s| s.to_string()).collect();
         let line_color_map = store_colors(&content_lines);
 
-        prettified = align_horizontal(prettified, style_map, _width, line_color_map, right_aligned);
+        prettified = align_horizontal(prettified, style, _width, line_color_map, right_aligned);
     }
 
     // align the content vertically based on the flag set in the style map
-    if style_map.get("vertical_alignment").unwrap() == "true" {
+    if style.vertical_alignment {
         prettified = align_vertical(
             prettified,
-            style_map,
+            style,
             height,
             &mut upper_bound,
             &mut lower_bound,
@@ -781,11 +972,9 @@ pub fn align_content(
     }
     prettified.push('\n');
 
-    let mut global_styles = STYLES.lock().unwrap();
-
-    global_styles.insert("upper_bound".to_string(), upper_bound.to_string());
-    global_styles.insert("lower_bound".to_string(), lower_bound.to_string());
-    drop(global_styles);
+    let mut bounds = BOUNDS.lock().unwrap();
+    *bounds = (upper_bound, lower_bound);
+    drop(bounds);
 
     return prettified;
 }
@@ -816,7 +1005,7 @@ pub fn syntax_highlighter(language: &str, code_section: String, theme: String, b
     // Highlight each line
     let mut highlighted = String::new();
     for line in LinesWithEndings::from(&code_section) {
-        let ranges: Vec<(Style, &str)> = h.highlight(line, &PS);
+        let ranges: Vec<(SyntectStyle, &str)> = h.highlight(line, &PS);
         let mut escaped = syntect::util::as_24_bit_terminal_escaped(&ranges[..], bg);
         escaped = escaped.replace("\t", "    ");
         highlighted.push_str(&escaped);
@@ -825,28 +1014,144 @@ pub fn syntax_highlighter(language: &str, code_section: String, theme: String, b
     highlighted
 }
 
+/// Returns true if the syntax highlighting theme is known
+pub fn has_theme(theme: &str) -> bool {
+    TS.themes.contains_key(theme)
+}
+
+/// Returns the names of all the syntax highlighting themes
+pub fn theme_names() -> Vec<String> {
+    TS.themes.keys().cloned().collect()
+}
+
+/// This function is used to syntax highlight the code for the HTML renderer
+/// It uses the same syntaxes and themes as the terminal renderer, and returns a `<pre>` block with inline styles
+/// Unknown themes fall back to the default `base16-ocean.dark` theme
+pub fn syntax_highlighter_html(language: &str, code_section: &str, theme: &str) -> String {
+    let syntax = PS
+        .find_syntax_by_extension(language)
+        .unwrap_or(PS.find_syntax_plain_text());
+    let theme = TS
+        .themes
+        .get(theme)
+        .unwrap_or(&TS.themes["base16-ocean.dark"]);
+
+    syntect::html::highlighted_html_for_string(code_section, &PS, syntax, theme)
+}
+
 /// This is used to get the upper and lower bounds of the content
 /// The upper and lower bounds are used for vertical alignment
 /// The upper bound is the number of blank lines at the beginning of the content
 /// The lower bound is the number of blank lines at the end of the content
-/// The bounds are stored in the global STYLES variable and are used fort scrolling
+/// The bounds are stored in the global BOUNDS variable and are used fort scrolling
 pub fn get_bounds() -> (u32, u32) {
-    let global_styles = STYLES.lock().unwrap();
+    let bounds = BOUNDS.lock().unwrap();
+    let (upper_bound, lower_bound) = *bounds;
+    drop(bounds);
 
-    let upper_bound = global_styles
-        .get("upper_bound")
-        .unwrap()
-        .parse::<u32>()
-        .unwrap();
-    let lower_bound = global_styles
-        .get("lower_bound")
-        .unwrap()
-        .parse::<u32>()
-        .unwrap();
+    return (upper_bound, lower_bound);
+}
 
-    drop(global_styles);
+/// This is used to keep rows at the bottom of the terminal free of the slide
+/// The slides are vertically aligned within the remaining rows
+pub fn reserve_rows(rows: u16) {
+    *RESERVED_ROWS.lock().unwrap() = rows;
+}
 
-    return (upper_bound, lower_bound);
+/// This is used to lay out the slides for a fixed size instead of the size of the terminal
+/// None goes back to the size of the terminal
+pub fn set_viewport(size: Option<(u16, u16)>) {
+    *VIEWPORT.lock().unwrap() = size;
+}
+
+/// This is used to get the size the slides are laid out for
+fn viewport_size() -> (u16, u16) {
+    match *VIEWPORT.lock().unwrap() {
+        Some(size) => size,
+        None => termion::terminal_size().unwrap_or((80, 24)),
+    }
+}
+
+/// This is used to reveal a slide fragment by fragment
+/// The next slides rendered show their first `revealed` fragments, usize::MAX shows them whole
+/// When `incremental` is true, the items of the lists are revealed one at a time
+pub fn set_reveal(revealed: usize, incremental: bool) {
+    *REVEAL.lock().unwrap() = (revealed, incremental);
+}
+
+/// This is used to get the number of fragments of the last slide rendered
+/// A slide without pauses has no fragments, it is shown whole
+pub fn get_fragments() -> usize {
+    *FRAGMENTS.lock().unwrap()
+}
+
+/// Returns the source offsets at which the fragments of a slide start, in order
+/// A fragment starts at every `<!-- pause -->` marker between the blocks of the slide,
+/// at every item of its lists when the slide is incremental,
+/// and at every block but the first when `clear` is set in the style
+/// The HTML renderer uses the same fragments as the terminal
+pub fn fragment_starts(root: &mdast::Node, incremental: bool, clear: bool) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let children = match root {
+        mdast::Node::Root(root) => &root.children,
+        _ => return starts,
+    };
+    // The block after an incremental list is revealed on its own, rather than with the last item
+    let mut after_list = false;
+    // A pause has started a fragment, which the next block belongs to
+    let mut paused = false;
+    for (i, child) in children.iter().enumerate() {
+        let start = match child.position() {
+            Some(position) => position.start.offset,
+            None => continue,
+        };
+        match child {
+            mdast::Node::Html(html) if PAUSE.is_match(html.value.trim()) => {
+                if !paused {
+                    starts.push(start);
+                }
+                paused = true;
+                continue;
+            }
+            mdast::Node::List(list) if incremental || clear => {
+                // The first item of the first block is shown up front when the slide is cleared
+                let skip = if clear && i == 0 { 1 } else { 0 };
+                for (j, item) in list.children.iter().enumerate().skip(skip) {
+                    match item.position() {
+                        Some(_) if j == 0 && paused => {}
+                        Some(position) => starts.push(position.start.offset),
+                        None => {}
+                    }
+                }
+                after_list = true;
+            }
+            _ if ((clear && i > 0) || after_list) && !paused => {
+                starts.push(start);
+                after_list = false;
+            }
+            _ => after_list = false,
+        }
+        paused = false;
+    }
+    // A pause at the end of the slide would reveal nothing
+    if paused {
+        starts.pop();
+    }
+    starts.dedup();
+    starts
+}
+
+/// This is used to check whether a block is concealed, because its fragment is not revealed yet
+fn is_concealed(node: &mdast::Node) -> bool {
+    match (*CONCEALED_FROM.lock().unwrap(), node.position()) {
+        (Some(from), Some(position)) => position.start.offset >= from,
+        _ => false,
+    }
+}
+
+/// This is used to set the project directory, which the paths of the images are relative to
+pub fn set_base_dir(dir: &Path) {
+    *BASE_DIR.lock().unwrap() = dir.to_path_buf();
 }
 
 /// This function is used to get the code from the global CODES variable
@@ -871,12 +1176,11 @@ pub fn get_code(index: usize) -> Result<(String, String), Box<dyn std::error::Er
 
 pub fn prettify(
     md_text: &str,
-    style_map: &HashMap<String, String>,
+    style: &Style,
     highlight_line_num: u32,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let map = style_map.clone();
     let mut global_styles = STYLES.lock().unwrap();
-    *global_styles = map;
+    *global_styles = style.clone();
     drop(global_styles);
 
     let mut codes = CODES.lock().unwrap();
@@ -884,6 +1188,8 @@ pub fn prettify(
     *codes = BTreeMap::new();
     drop(codes);
 
+    graphics::reset(style.image_protocol);
+
     let mut lines = md_text.lines();
     // let mut front_matter = Vec::new();
 
@@ -901,12 +1207,19 @@ pub fn prettify(
         String::new()
     };
 
-    let parsed = markdown::to_mdast(&md_text, &markdown::ParseOptions::default());
+    // GFM is enabled for tables, task lists, strikethrough, autolinks and footnotes
+    let parsed = markdown::to_mdast(&md_text, &markdown::ParseOptions::gfm());
     let mut prettified = String::new();
 
     match parsed {
         Err(err) => return Err(format!("Error parsing markdown: {}", err).into()),
         Ok(node) => {
+            // The blocks past the revealed fragments are concealed
+            let (revealed, incremental) = *REVEAL.lock().unwrap();
+            let starts = fragment_starts(&node, incremental, style.clear);
+            *FRAGMENTS.lock().unwrap() = starts.len();
+            *CONCEALED_FROM.lock().unwrap() = starts.get(revealed).copied();
+
             let result = visit_md_node(node, 0);
             if let Some(text) = result {
                 prettified.push_str(&text);
@@ -919,5 +1232,128 @@ pub fn prettify(
         prettified.pop();
     }
 
-    return Ok(align_content(prettified, style_map, highlight_line_num));
+    return Ok(align_content(prettified, style, highlight_line_num));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the first line of every fragment of a slide but the first.
+    fn fragments(text: &str, incremental: bool, clear: bool) -> Vec<&str> {
+        let root = markdown::to_mdast(text, &markdown::ParseOptions::gfm()).unwrap();
+        fragment_starts(&root, incremental, clear)
+            .into_iter()
+            .map(|start| text[start..].lines().next().unwrap())
+            .collect()
+    }
+
+    /// Returns the lines of a table drawn within the given width, without their colors.
+    fn table_lines(text: &str, max_width: usize) -> Vec<String> {
+        let root = markdown::to_mdast(text, &markdown::ParseOptions::gfm()).unwrap();
+        let table = match root.children().unwrap()[0].clone() {
+            mdast::Node::Table(table) => table,
+            node => panic!("not a table: {:?}", node),
+        };
+        draw_table(table, 0, &Style::default(), max_width)
+            .lines()
+            .skip(1)
+            .map(strip_ansi_codes)
+            .collect()
+    }
+
+    #[test]
+    fn table_cells_follow_the_alignment_of_their_column() {
+        let text = "| a | b | c |\n|:--|:-:|--:|\n| xxx | yyy | zzz |\n";
+        assert_eq!(
+            table_lines(text, 80),
+            vec![
+                "┌─────┬─────┬─────┐",
+                "│ a   │  b  │   c │",
+                "├─────┼─────┼─────┤",
+                "│ xxx │ yyy │ zzz │",
+                "└─────┴─────┴─────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_columns_fit_wide_chars() {
+        let text = "| 名前 | x |\n|---|---|\n| 日本語 | y |\n";
+        assert_eq!(
+            table_lines(text, 80),
+            vec![
+                "┌────────┬───┐",
+                "│ 名前   │ x │",
+                "├────────┼───┤",
+                "│ 日本語 │ y │",
+                "└────────┴───┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn wide_table_is_wrapped_to_the_width() {
+        let text = "| id | description |\n|---|---|\n| 1 | a rather long description |\n";
+        let lines = table_lines(text, 20);
+        assert_eq!(
+            lines,
+            vec![
+                "┌────┬─────────────┐",
+                "│ id │ description │",
+                "├────┼─────────────┤",
+                "│ 1  │ a rather    │",
+                "│    │ long        │",
+                "│    │ description │",
+                "└────┴─────────────┘",
+            ]
+        );
+        assert!(lines.iter().all(|line| visible_width(line) <= 20));
+    }
+
+    #[test]
+    fn table_too_wide_to_wrap_is_clipped() {
+        let text =
+            "| a | b | c | d | e | f |\n|---|---|---|---|---|---|\n| 1 | 2 | 3 | 4 | 5 | 6 |\n";
+        let lines = table_lines(text, 12);
+        assert!(lines.iter().all(|line| visible_width(line) <= 12));
+        assert!(lines.iter().all(|line| line.ends_with(CLIP_MARKER)));
+    }
+
+    #[test]
+    fn fragments_start_at_pauses() {
+        let text = "# Title\n\n<!-- pause -->\n\nOne\n\n<!-- pause -->\n\n<!-- pause -->\n\nTwo\n";
+        assert_eq!(
+            fragments(text, false, false),
+            vec!["<!-- pause -->", "<!-- pause -->"]
+        );
+    }
+
+    #[test]
+    fn pause_at_end_reveals_nothing() {
+        assert!(fragments("# Title\n\n<!-- pause -->\n", false, false).is_empty());
+    }
+
+    #[test]
+    fn no_fragments_without_pauses() {
+        assert!(fragments("# Title\n\n- a\n- b\n", false, false).is_empty());
+    }
+
+    #[test]
+    fn incremental_list_items_are_fragments() {
+        let text = "# Title\n\n- a\n- b\n\nAfter\n";
+        assert_eq!(fragments(text, true, false), vec!["- a", "- b", "After"]);
+    }
+
+    #[test]
+    fn first_item_after_pause_belongs_to_it() {
+        let text = "# Title\n\n<!-- pause -->\n\n- a\n- b\n";
+        assert_eq!(fragments(text, true, false), vec!["<!-- pause -->", "- b"]);
+    }
+
+    #[test]
+    fn clear_reveals_every_block() {
+        let text = "- a\n- b\n\nOne\n\nTwo\n";
+        assert_eq!(fragments(text, false, true), vec!["- b", "One", "Two"]);
+    }
 }
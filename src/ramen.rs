@@ -1,129 +1,793 @@
 /// Ramen is a simple code runner for various languages.
 /// It is designed to be used in a terminal env to run code in a sandboxed environment.
 /// It takes in the code and the language and returns the output of the code.
-/// The runtimes are defined in a separare environment variable, dependent on the host system.
-use std::collections::HashMap;
-use std::io::Write;
+/// The runners of the languages are defined in the runtime_map of style.yml, dependent on the host
+/// system: the extension of the source file, and the commands compiling, running and cleaning up.
+/// Every run happens in a private temporary directory, which is removed afterwards. The programs
+/// are killed once they run past the timeout, and on Linux their memory and CPU time are limited.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn run_code(
-    lang: String,
-    code: String,
-    runtime_map: &HashMap<String, String>,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let output = match lang.as_str() {
-        "python" | "py" => run_python_code(code, runtime_map.get("python").unwrap().as_str()),
-        "javascript" | "js" => {
-            run_javascript_code(code, runtime_map.get("javascript").unwrap().as_str())
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use tempfile::TempDir;
+
+/// How often a running program is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long the output of a program is still read once it is stopped.
+/// A process that left the process group of the program can keep its pipes open for good.
+const OUTPUT_DEADLINE: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    /// The process ids of the running programs, so that the presenter can stop them.
+    /// Every program leads its own process group, which is killed along with it.
+    static ref RUNNING: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+}
+
+/// The limits a program runs within.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// The wall-clock time a program can run for before it is killed.
+    pub timeout: Duration,
+    /// The wall-clock time a compiler can run for before it is killed.
+    pub compile_timeout: Duration,
+    /// The memory a program can allocate, in megabytes. 0 disables the limit. Only enforced on Linux,
+    /// and not on compilers, which need more memory than the programs they build.
+    pub memory: u64,
+}
+
+/// How the code blocks are run, from the style of the deck.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The runners of the languages, by language.
+    pub runners: BTreeMap<String, Runner>,
+    pub limits: Limits,
+    /// The edition Rust code blocks are compiled with.
+    pub rust_edition: String,
+}
+
+/// The steps of a run, which have their own timeouts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Step {
+    Compile,
+    Run,
+    Cleanup,
+}
+
+impl Step {
+    fn timeout(self, limits: &Limits) -> Duration {
+        match self {
+            Step::Compile => limits.compile_timeout,
+            Step::Run | Step::Cleanup => limits.timeout,
         }
-        "ruby" | "rb" => run_ruby_code(code, runtime_map.get("ruby").unwrap().as_str()),
-        "c" | "c++" | "cpp" => run_c_code(code, runtime_map.get("c").unwrap().as_str()),
-        "java" => run_java_code(code, runtime_map.get("java").unwrap().as_str()),
-        "rs" | "rust" => run_rust_code(code, runtime_map.get("rust").unwrap().as_str()),
-        _ => Err("Language not supported".to_string())?,
-    };
-    output
+    }
+
+    fn memory(self, limits: &Limits) -> u64 {
+        match self {
+            Step::Compile => 0,
+            Step::Run | Step::Cleanup => limits.memory,
+        }
+    }
 }
 
-pub fn run_python_code(code: String, runtime: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("temp.py")?;
-    file.write_all(code.as_bytes())?;
+/// Stop all the running programs.
+pub fn stop_all() {
+    // The lock is held while the groups are killed, as a program is only reaped once it is removed.
+    let mut running = RUNNING.lock().unwrap();
+    for pid in running.drain(..) {
+        kill_group(pid);
+    }
+}
 
-    let output = std::process::Command::new(runtime)
-        .arg("temp.py")
-        .output()?;
+/// Kill a program along with the processes it started.
+fn kill_group(pid: u32) {
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
 
-    std::fs::remove_file("temp.py")?;
+/// Waits for a program to exit, without reaping it.
+/// The process id of the program is also the id of its group, and it cannot be reused until the
+/// program is reaped, so its group is killed after this returns and before the program is reaped.
+/// # Arguments
+/// * `pid` - The process id of the program.
+/// * `block` - Whether to wait for the program to exit, or to return right away.
+/// # Returns
+/// Whether the program has exited.
+fn wait_exited(pid: u32, block: bool) -> io::Result<bool> {
+    let mut flags = libc::WEXITED | libc::WNOWAIT;
+    if !block {
+        flags |= libc::WNOHANG;
+    }
+    loop {
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, flags) };
+        if result == 0 {
+            // Without WNOHANG the program has exited, with it the process id is only set once it has.
+            return Ok(unsafe { info.si_pid() } != 0);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// Removes a program from the running programs.
+/// Returns false if the program was already removed, which means it was stopped by the presenter.
+fn unregister(pid: u32) -> bool {
+    let mut running = RUNNING.lock().unwrap();
+    match running.iter().position(|running_pid| *running_pid == pid) {
+        Some(index) => {
+            running.remove(index);
+            true
+        }
+        None => false,
+    }
 }
 
-pub fn run_javascript_code(
-    code: String,
-    runtime: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("temp.js")?;
-    file.write_all(code.as_bytes())?;
+/// Applies the limits to the process about to run a program.
+/// This runs in the forked child, so it only makes async-signal-safe calls.
+/// # Arguments
+/// * `memory` - The memory the process can allocate, in megabytes. 0 disables the limit.
+/// * `timeout` - The wall-clock time the process can run for.
+fn limit_process(memory: u64, timeout: Duration) -> io::Result<()> {
+    // A process group of its own lets the program be killed along with its children.
+    if unsafe { libc::setpgid(0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
 
-    let output = std::process::Command::new(runtime)
-        .arg("temp.js")
-        .output()?;
+    #[cfg(target_os = "linux")]
+    {
+        let set_limit = |resource, value: u64| {
+            let limit = libc::rlimit {
+                rlim_cur: value as libc::rlim_t,
+                rlim_max: value as libc::rlim_t,
+            };
+            if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        // The data segment is limited rather than the address space, runtimes such as the JVM
+        // reserve far more address space than they use.
+        if memory > 0 {
+            set_limit(libc::RLIMIT_DATA, memory.saturating_mul(1024 * 1024))?;
+        }
+        // The CPU time can not exceed the wall-clock time, this only catches busy loops in
+        // processes that escape the process group.
+        set_limit(libc::RLIMIT_CPU, timeout.as_secs().max(1) + 1)?;
+    }
 
-    std::fs::remove_file("temp.js")?;
+    Ok(())
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// The stream a line of output was printed to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
 }
 
-pub fn run_ruby_code(code: String, runtime: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("temp.rb")?;
-    file.write_all(code.as_bytes())?;
+/// Called with every line a program prints, as soon as it is printed.
+type OutputHandler = Arc<dyn Fn(Stream, &str) + Send + Sync>;
 
-    let output = std::process::Command::new(runtime)
-        .arg("temp.rb")
-        .output()?;
+/// The result of running a code block.
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    pub stdout: String,
+    pub stderr: String,
+    /// The exit code of the program, None if it was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// The signal that killed the program.
+    pub signal: Option<i32>,
+    /// The wall-clock time the program ran for.
+    pub duration: Duration,
+}
 
-    std::fs::remove_file("temp.rb")?;
+impl RunResult {
+    /// Returns true if the program exited with 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    /// Describes how the program exited and how long it ran for, such as `exit code 1 in 0.12s`.
+    pub fn status(&self) -> String {
+        let status = match (self.exit_code, self.signal) {
+            (Some(code), _) => format!("exit code {}", code),
+            (None, Some(signal)) => format!("killed by signal {}", signal),
+            (None, None) => "killed".to_string(),
+        };
+        format!("{} in {:.2}s", status, self.duration.as_secs_f64())
+    }
 }
 
-pub fn run_c_code(code: String, runtime: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("temp.c")?;
-    file.write_all(code.as_bytes())?;
+/// A private temporary directory the code is written to and run in.
+/// The directory and everything in it is removed when the sandbox is dropped.
+struct Sandbox {
+    dir: TempDir,
+    limits: Limits,
+    on_output: OutputHandler,
+}
+
+impl Sandbox {
+    fn new(limits: Limits, on_output: OutputHandler) -> io::Result<Sandbox> {
+        Ok(Sandbox {
+            dir: tempfile::Builder::new().prefix("ramen").tempdir()?,
+            limits,
+            on_output,
+        })
+    }
 
-    let output = std::process::Command::new(runtime)
-        .arg("temp.c")
-        .arg("-o")
-        .arg("temp")
-        .output()?;
+    /// Returns the path of a file in the sandbox.
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
 
-    if output.status.success() {
-        let output = std::process::Command::new("./temp").output()?;
-        std::fs::remove_file("temp.c")?;
-        std::fs::remove_file("temp")?;
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        std::fs::remove_file("temp.c")?;
-        std::fs::remove_file("temp")?;
-        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    /// Write a file into the sandbox.
+    fn write(&self, name: &str, code: &str) -> io::Result<()> {
+        fs::write(self.path(name), code)
+    }
+
+    /// Run a program in the sandbox, within its limits.
+    /// # Arguments
+    /// * `command` - The program to run, followed by its arguments.
+    /// * `step` - The step of the run. The output of the cleanup is not streamed to the output handler.
+    /// # Returns
+    /// The result of the program, or an error if it could not start, timed out or was stopped.
+    fn run(&self, command: &[String], step: Step) -> Result<RunResult, Box<dyn Error>> {
+        use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+        let (program, args) = command
+            .split_first()
+            .ok_or("Empty command in the runtime_map")?;
+        let timeout = step.timeout(&self.limits);
+        let memory = step.memory(&self.limits);
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(self.dir.path())
+            // Python buffers its output when it is not printing to a terminal.
+            .env("PYTHONUNBUFFERED", "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        unsafe {
+            command.pre_exec(move || limit_process(memory, timeout));
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| format!("Could not run {}: {}", program, err))?;
+        let pid = child.id();
+        RUNNING.lock().unwrap().push(pid);
+
+        // The output is read line by line in separate threads, so that it is handled as soon as it
+        // is printed, and a program filling a pipe does not block.
+        // The output is shared with the reader, which stops once the output is collected.
+        let read = |pipe: Box<dyn Read + Send>, kind: Stream| {
+            let on_output = (step != Step::Cleanup).then(|| self.on_output.clone());
+            let output = Arc::new(Mutex::new(Some(String::new())));
+            let (done, finished) = channel();
+            let shared = output.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(pipe);
+                let mut line = Vec::new();
+                while matches!(reader.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+                    let text = String::from_utf8_lossy(&line);
+                    match shared.lock().unwrap().as_mut() {
+                        Some(output) => output.push_str(&text),
+                        None => return,
+                    }
+                    if let Some(on_output) = &on_output {
+                        on_output(kind, text.trim_end_matches(['\n', '\r']));
+                    }
+                    line.clear();
+                }
+                let _ = done.send(());
+            });
+            (output, finished)
+        };
+        let stdout = read(Box::new(child.stdout.take().unwrap()), Stream::Stdout);
+        let stderr = read(Box::new(child.stderr.take().unwrap()), Stream::Stderr);
+
+        let started = Instant::now();
+        let mut timed_out = false;
+        while !wait_exited(pid, false)? {
+            if started.elapsed() >= timeout {
+                timed_out = true;
+                kill_group(pid);
+                wait_exited(pid, true)?;
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        // The processes left in the group by the program would keep its pipes open.
+        // The program is only reaped once its group is killed and it is no longer running,
+        // so that neither kills another group that took its process id.
+        kill_group(pid);
+        let stopped = !unregister(pid);
+        let status = child.wait()?;
+
+        // The rest of the output is left behind when a process that left the group keeps a pipe open.
+        let deadline = Instant::now() + OUTPUT_DEADLINE;
+        let collect = |(output, finished): (Arc<Mutex<Option<String>>>, Receiver<()>)| {
+            let _ = finished.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+            output.lock().unwrap().take().unwrap_or_default()
+        };
+        let result = RunResult {
+            stdout: collect(stdout),
+            stderr: collect(stderr),
+            exit_code: status.code(),
+            signal: status.signal(),
+            duration: started.elapsed(),
+        };
+
+        if timed_out {
+            return Err(format!("Timed out after {}s", timeout.as_secs_f32()).into());
+        }
+        if stopped {
+            return Err("Stopped".into());
+        }
+        Ok(result)
     }
 }
 
-pub fn run_java_code(code: String, runtime: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("Main.java")?;
-    file.write_all(code.as_bytes())?;
+/// A runner describes how the code blocks of a language are run.
+/// The commands are split on whitespace, with quotes grouping words, and the placeholders are
+/// replaced in every word:
+/// * `{file}` - The name of the source file, in the sandbox directory.
+/// * `{bin}` - The path of the compiled program.
+/// * `{dir}` - The path of the sandbox directory.
+/// * `{edition}` - The edition Rust code blocks are compiled with.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Runner {
+    /// The extension of the source file, without the dot.
+    pub extension: String,
+    /// The name of the source file, for languages that expect a specific name. Defaults to `main.<extension>`.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Compiles the source file, before it is run.
+    #[serde(default)]
+    pub compile: Option<String>,
+    /// Runs the program.
+    pub run: String,
+    /// Runs after the program, even if it failed. The sandbox directory is removed on its own.
+    #[serde(default)]
+    pub cleanup: Option<String>,
+    /// Other names of the language, used in the info string of the code blocks.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
 
-    let output = std::process::Command::new(runtime)
-        .arg("Main.java")
-        .output()?;
+impl Runner {
+    /// A runner for an interpreter, which runs the source file directly.
+    fn interpreter(extension: &str, command: &str, aliases: &[&str]) -> Runner {
+        Runner {
+            extension: extension.to_string(),
+            file: None,
+            compile: None,
+            run: format!("{} {{file}}", command),
+            cleanup: None,
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+        }
+    }
 
-    if output.status.success() {
-        let output = std::process::Command::new("java").arg("Main").output()?;
-        std::fs::remove_file("Main.java")?;
-        std::fs::remove_file("Main.class")?;
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        std::fs::remove_file("Main.java")?;
-        std::fs::remove_file("Main.class")?;
-        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    /// A runner for a compiler, which builds `{bin}` from the source file.
+    fn compiler(extension: &str, compile: &str, aliases: &[&str]) -> Runner {
+        Runner {
+            compile: Some(compile.to_string()),
+            run: "{bin}".to_string(),
+            ..Runner::interpreter(extension, "", aliases)
+        }
     }
+
+    /// Returns the name of the source file.
+    fn file_name(&self) -> String {
+        self.file
+            .clone()
+            .unwrap_or_else(|| format!("main.{}", self.extension))
+    }
+}
+
+/// The default runners of the languages.
+pub fn default_runners() -> BTreeMap<String, Runner> {
+    let mut runners = BTreeMap::new();
+    runners.insert(
+        "python".to_string(),
+        Runner::interpreter("py", "python3", &["py"]),
+    );
+    runners.insert(
+        "javascript".to_string(),
+        Runner::interpreter("js", "node", &["js"]),
+    );
+    runners.insert(
+        "ruby".to_string(),
+        Runner::interpreter("rb", "ruby", &["rb"]),
+    );
+    runners.insert(
+        "c".to_string(),
+        Runner::compiler("c", "gcc {file} -o {bin}", &[]),
+    );
+    runners.insert(
+        "cpp".to_string(),
+        Runner::compiler("cpp", "g++ {file} -o {bin}", &["c++", "cc"]),
+    );
+    runners.insert(
+        "java".to_string(),
+        Runner {
+            // The java launcher compiles and runs a single source file, named after its class.
+            file: Some("Main.java".to_string()),
+            ..Runner::interpreter("java", "java", &[])
+        },
+    );
+    runners.insert(
+        "go".to_string(),
+        Runner::interpreter("go", "go run", &["golang"]),
+    );
+    runners.insert(
+        "rust".to_string(),
+        Runner::compiler("rs", "rustc --edition {edition} {file} -o {bin}", &["rs"]),
+    );
+    runners.insert(
+        "swift".to_string(),
+        Runner::interpreter("swift", "swift", &[]),
+    );
+    runners.insert(
+        "kotlin".to_string(),
+        Runner {
+            compile: Some("kotlinc {file} -include-runtime -d main.jar".to_string()),
+            run: "java -jar main.jar".to_string(),
+            ..Runner::interpreter("kt", "", &["kt"])
+        },
+    );
+    runners.insert(
+        "typescript".to_string(),
+        Runner {
+            compile: Some("tsc {file}".to_string()),
+            run: "node main.js".to_string(),
+            ..Runner::interpreter("ts", "", &["ts"])
+        },
+    );
+    runners
+}
+
+/// The runtimes of the older style files, which named the compiler of the languages rather than
+/// the command running them. They keep the default runner of their language.
+const LEGACY_RUNTIMES: &[(&str, &str)] = &[
+    ("c", "gcc"),
+    ("cpp", "g++"),
+    ("rust", "cargo run"),
+    ("kotlin", "kotlinc"),
+    ("typescript", "tsc"),
+];
+
+/// Parse the runners of a runtime map, over the default runners.
+/// A runner is either a mapping, or the command of an interpreter, such as `python3`, which is
+/// run with the source file.
+pub fn parse_runners(
+    runtime_map: BTreeMap<String, serde_yaml::Value>,
+) -> Result<BTreeMap<String, Runner>, String> {
+    let mut runners = default_runners();
+    for (language, value) in runtime_map {
+        let runner = match value {
+            serde_yaml::Value::String(command) => {
+                if LEGACY_RUNTIMES.contains(&(language.as_str(), command.trim())) {
+                    continue;
+                }
+                // A known language keeps its extension and aliases.
+                let (extension, aliases) = match runners.get(&language) {
+                    Some(runner) => (runner.extension.clone(), runner.aliases.clone()),
+                    None => (language.clone(), Vec::new()),
+                };
+                Runner {
+                    aliases,
+                    ..Runner::interpreter(&extension, &command, &[])
+                }
+            }
+            value => {
+                serde_yaml::from_value(value).map_err(|err| format!("{}: {}", language, err))?
+            }
+        };
+        runners.insert(language, runner);
+    }
+    Ok(runners)
 }
 
-pub fn run_rust_code(code: String, runtime: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut file = std::fs::File::create("temp.rs")?;
-    file.write_all(code.as_bytes())?;
+/// Returns the runner of a language along with the name of the language, by its name or one of
+/// its aliases.
+fn find_runner<'a>(
+    runners: &'a BTreeMap<String, Runner>,
+    lang: &str,
+) -> Result<(&'a str, &'a Runner), Box<dyn std::error::Error>> {
+    runners
+        .get_key_value(lang)
+        .or_else(|| {
+            runners
+                .iter()
+                .find(|(_, runner)| runner.aliases.iter().any(|alias| alias == lang))
+        })
+        .map(|(name, runner)| (name.as_str(), runner))
+        .ok_or_else(|| format!("No runtime for {} in the runtime_map of style.yml", lang).into())
+}
+
+/// Split a command into words, on whitespace outside of quotes.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Runs a code block in a sandbox.
+/// # Arguments
+/// * `lang` - The language of the code block.
+/// * `code` - The code to run.
+/// * `config` - How the code blocks are run.
+/// * `on_output` - Called with every line the code prints, while it runs.
+/// # Returns
+/// The result of the run, or an error if the code could not run, timed out or was stopped.
+pub fn run_code<F>(
+    lang: String,
+    code: String,
+    config: &Config,
+    on_output: F,
+) -> Result<RunResult, Box<dyn std::error::Error>>
+where
+    F: Fn(Stream, &str) + Send + Sync + 'static,
+{
+    let (name, runner) = find_runner(&config.runners, &lang)?;
+    let sandbox = Sandbox::new(config.limits, Arc::new(on_output))?;
+
+    // Rust code blocks with dependencies are built in a Cargo project.
+    if name == "rust" {
+        if let Some(dependencies) = cargo_dependencies(&code) {
+            return run_cargo_code(&code, &dependencies, &config.rust_edition, &sandbox);
+        }
+    }
+
+    let file = runner.file_name();
+    sandbox.write(&file, &code)?;
+
+    let dir = sandbox.dir.path().to_string_lossy().to_string();
+    let bin = sandbox.path("main").to_string_lossy().to_string();
+    let command = |template: &str| -> Vec<String> {
+        split_command(template)
+            .into_iter()
+            .map(|word| {
+                word.replace("{file}", &file)
+                    .replace("{bin}", &bin)
+                    .replace("{dir}", &dir)
+                    .replace("{edition}", &config.rust_edition)
+            })
+            .collect()
+    };
+
+    let result = match &runner.compile {
+        Some(compile) => compile_and_run(&sandbox, &command(compile), &command(&runner.run)),
+        None => sandbox.run(&command(&runner.run), Step::Run),
+    };
+
+    // The output of the cleanup is not shown.
+    if let Some(cleanup) = &runner.cleanup {
+        let _ = sandbox.run(&command(cleanup), Step::Cleanup);
+    }
+    result
+}
 
-    let output = std::process::Command::new(runtime)
-        .arg("temp.rs")
-        .output()?;
+/// Returns the dependencies declared in the header of a Rust code block.
+/// The header is made of the comments at the top of the block, starting with a `[dependencies]`
+/// line, and is read as the dependencies of a Cargo manifest:
+/// ```text
+/// // [dependencies]
+/// // rand = "0.8"
+/// use rand::Rng;
+/// ```
+fn cargo_dependencies(code: &str) -> Option<String> {
+    let header: Vec<&str> = code
+        .lines()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| line.starts_with("//") && !line.starts_with("///"))
+        .map(|line| line.trim_start_matches("//").trim())
+        .collect();
+
+    match header.first() {
+        Some(&"[dependencies]") => Some(header.join("\n") + "\n"),
+        _ => None,
+    }
+}
+
+/// Returns the 64-bit FNV-1a hash of some text.
+/// The hash names the cached Cargo projects and their programs, so it must not change from one
+/// release of Rust to the next, as the hasher of the standard library may.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns the directory of the scratch Cargo project used for a set of dependencies.
+/// The projects are kept in the cache directory of the user, so the dependencies are only built
+/// once, rather than on every run.
+fn cargo_project_dir(manifest: &str) -> PathBuf {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+
+    cache
+        .join("dough")
+        .join("cargo")
+        .join(format!("{:016x}", fnv1a(manifest)))
+}
+
+/// Write a file of a scratch Cargo project whole, so that a build running at the same time never
+/// reads it half written.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+/// Builds a Rust code block with its dependencies in a scratch Cargo project, and runs it in the
+/// sandbox.
+/// The code blocks with the same dependencies share a project, every block is a program of its own
+/// in it, named after its code. Cargo locks the project while it builds, so blocks run at the same
+/// time are built one after the other, and each runs its own program.
+fn run_cargo_code(
+    code: &str,
+    dependencies: &str,
+    edition: &str,
+    sandbox: &Sandbox,
+) -> Result<RunResult, Box<dyn std::error::Error>> {
+    let manifest = format!(
+        "[package]\nname = \"ramen\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n{}",
+        edition, dependencies
+    );
+    let project = cargo_project_dir(&manifest);
+    let program = format!("block_{:016x}", fnv1a(code));
+    fs::create_dir_all(project.join("src").join("bin"))?;
+    write_atomically(&project.join("Cargo.toml"), &manifest)?;
+    write_atomically(
+        &project
+            .join("src")
+            .join("bin")
+            .join(format!("{}.rs", program)),
+        code,
+    )?;
+
+    let manifest_path = project.join("Cargo.toml").to_string_lossy().to_string();
+    let build = [
+        "cargo".to_string(),
+        "build".to_string(),
+        "--quiet".to_string(),
+        "--manifest-path".to_string(),
+        manifest_path,
+        "--bin".to_string(),
+        program.clone(),
+    ];
+    let binary = project.join("target").join("debug").join(&program);
+    compile_and_run(sandbox, &build, &[binary.to_string_lossy().to_string()])
+}
+
+/// Compiles a program, then runs it if it compiled.
+/// The result of the compiler is returned when the compilation fails. Otherwise the output of the
+/// compiler, such as its warnings, comes before the output of the program, and the duration covers
+/// both steps.
+fn compile_and_run(
+    sandbox: &Sandbox,
+    compile: &[String],
+    run: &[String],
+) -> Result<RunResult, Box<dyn std::error::Error>> {
+    let compiled = sandbox.run(compile, Step::Compile)?;
+    if !compiled.success() {
+        return Ok(compiled);
+    }
+
+    let mut result = sandbox.run(run, Step::Run)?;
+    result.stdout = compiled.stdout + &result.stdout;
+    result.stderr = compiled.stderr + &result.stderr;
+    result.duration += compiled.duration;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> Sandbox {
+        let limits = Limits {
+            timeout: Duration::from_secs(5),
+            compile_timeout: Duration::from_secs(5),
+            memory: u64::MAX,
+        };
+        Sandbox::new(limits, Arc::new(|_, _| {})).unwrap()
+    }
+
+    fn command(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn run_collects_output_and_status() {
+        let result = sandbox()
+            .run(&command("echo out; echo err >&2; exit 3"), Step::Run)
+            .unwrap();
+        assert_eq!(result.stdout, "out\n");
+        assert_eq!(result.stderr, "err\n");
+        assert_eq!(result.exit_code, Some(3));
+    }
+
+    #[test]
+    fn run_returns_when_a_detached_process_keeps_the_pipes() {
+        let started = Instant::now();
+        let result = sandbox()
+            .run(
+                &command("echo before; setsid sleep 10 & echo after"),
+                Step::Run,
+            )
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert!(result.stdout.contains("before"), "{}", result.stdout);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn memory_limit_applies_to_programs_not_compilers() {
+        let mut sandbox = sandbox();
+        sandbox.limits.memory = 64;
+        let limit = |step| sandbox.run(&command("ulimit -d"), step).unwrap().stdout;
+        assert_eq!(limit(Step::Run), "65536\n");
+        assert_eq!(limit(Step::Compile), "unlimited\n");
+    }
 
-    if output.status.success() {
-        let output = std::process::Command::new("./temp").output()?;
-        std::fs::remove_file("temp.rs")?;
-        std::fs::remove_file("temp")?;
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        std::fs::remove_file("temp.rs")?;
-        std::fs::remove_file("temp")?;
-        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    #[test]
+    fn run_kills_the_group_of_a_program_that_times_out() {
+        let mut sandbox = sandbox();
+        sandbox.limits.timeout = Duration::from_millis(300);
+        let started = Instant::now();
+        let err = sandbox
+            .run(&command("sleep 10 & sleep 10"), Step::Run)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Timed out after 0.3s");
+        // The sleep left in the group is killed too, so the pipes are closed right away.
+        assert!(started.elapsed() < Duration::from_secs(3));
     }
 }
@@ -0,0 +1,210 @@
+/// The recorder writes a presentation to an asciicast file, the format of asciinema, as it is shown.
+/// The presenter prints its frames through the output of the recorder, which passes them on to the
+/// terminal and, while a recording runs, writes every frame to the file with the time it was printed.
+/// The frames are the same bytes the terminal receives, so the recording plays back the talk exactly.
+use std::fs::File;
+use std::io::{self, stdout, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+lazy_static! {
+    /// The recording of the presentation, None when it is not recorded
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+}
+
+/// The first line of an asciicast v2 file.
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    title: &'a str,
+    env: Env,
+}
+
+/// The environment of the terminal the presentation was recorded in, for the players.
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct Env {
+    term: String,
+    shell: String,
+}
+
+/// A presentation being recorded.
+struct Recording {
+    file: BufWriter<File>,
+    started: Instant,
+    // The size of the terminal in the last frame, a frame printed at another size is preceded by a resize.
+    size: (u16, u16),
+    // Whether the output of the code blocks is part of the recording.
+    code_output: bool,
+    // The bytes printed since the last frame.
+    pending: Vec<u8>,
+}
+
+impl Recording {
+    /// Write an event, on a line of its own, and flush it so that the file is whole if the presenter stops.
+    fn event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        let event = serde_json::to_string(&(time, kind, data))?;
+        writeln!(self.file, "{}", event)?;
+        self.file.flush()
+    }
+
+    /// Write the bytes printed since the last frame as a frame.
+    /// The bytes of a character cut in two by the end of the frame are kept for the next frame.
+    fn frame(&mut self) -> io::Result<()> {
+        let size = termion::terminal_size()?;
+        if size != self.size {
+            self.size = size;
+            self.event("r", &format!("{}x{}", size.0, size.1))?;
+        }
+
+        match take_frame(&mut self.pending) {
+            Some(frame) => self.event("o", &frame),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Takes the text of a frame out of the bytes printed since the last frame.
+/// The bytes of a character cut in two at the end are left for the next frame, other invalid bytes
+/// are replaced. Returns None if there is nothing to write yet.
+fn take_frame(pending: &mut Vec<u8>) -> Option<String> {
+    let complete = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    if complete == 0 {
+        return None;
+    }
+    // The terminal returns to the first column on every new line, the players do as they are told.
+    let frame: Vec<u8> = pending.drain(..complete).collect();
+    Some(String::from_utf8_lossy(&frame).replace('\n', "\r\n"))
+}
+
+/// Start recording the presentation.
+/// # Arguments
+/// * `path` - The path of the asciicast file. It is replaced if it exists.
+/// * `title` - The title of the recording.
+/// * `code_output` - Whether the output of the code blocks is part of the recording.
+/// # Returns
+/// A result indicating whether the file could be created.
+pub fn start(path: &Path, title: &str, code_output: bool) -> io::Result<()> {
+    let (width, height) = termion::terminal_size()?;
+    let mut file = BufWriter::new(File::create(path)?);
+    let header = Header {
+        version: 2,
+        width,
+        height,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        title,
+        env: Env {
+            term: std::env::var("TERM").unwrap_or_default(),
+            shell: std::env::var("SHELL").unwrap_or_default(),
+        },
+    };
+    writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+    let mut recording = Recording {
+        file,
+        started: Instant::now(),
+        size: (width, height),
+        code_output,
+        pending: Vec::new(),
+    };
+    // The cursor is hidden in the terminal before the recording starts, and in the players too.
+    recording
+        .pending
+        .extend(termion::cursor::Hide.to_string().bytes());
+    recording.frame()?;
+    *RECORDING.lock().unwrap() = Some(recording);
+    Ok(())
+}
+
+/// Stop recording the presentation, and write what is left of its last frame.
+pub fn stop() -> io::Result<()> {
+    match RECORDING.lock().unwrap().take() {
+        Some(mut recording) => recording.frame(),
+        None => Ok(()),
+    }
+}
+
+/// Returns the output the frames of the presentation are printed to.
+pub fn output() -> Output {
+    Output { code: false }
+}
+
+/// Returns the output the output of the code blocks is printed to.
+/// It is left out of the recording unless the recording includes it, its rows stay blank.
+pub fn code_output() -> Output {
+    Output { code: true }
+}
+
+/// Prints to the terminal, and to the recording if the presentation is recorded.
+/// A frame is recorded every time the output is flushed.
+pub struct Output {
+    code: bool,
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = stdout().write(buf)?;
+        if let Some(recording) = RECORDING.lock().unwrap().as_mut() {
+            if !self.code || recording.code_output {
+                recording.pending.extend_from_slice(&buf[..written]);
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        stdout().flush()?;
+        match RECORDING.lock().unwrap().as_mut() {
+            Some(recording) => recording.frame(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_keeps_a_character_cut_in_two_for_the_next_frame() {
+        let bytes = "a日\nb".as_bytes();
+        // The end of the first frame cuts the second byte of `日`.
+        let mut pending = bytes[..3].to_vec();
+        assert_eq!(take_frame(&mut pending).as_deref(), Some("a"));
+        assert_eq!(pending, &bytes[1..3]);
+
+        pending.extend_from_slice(&bytes[3..]);
+        assert_eq!(take_frame(&mut pending).as_deref(), Some("日\r\nb"));
+        assert!(pending.is_empty());
+        assert_eq!(take_frame(&mut pending), None);
+    }
+
+    #[test]
+    fn frame_of_a_lone_partial_character_waits() {
+        let mut pending = "✔".as_bytes()[..2].to_vec();
+        assert_eq!(take_frame(&mut pending), None);
+        assert_eq!(pending.len(), 2);
+    }
+
+    #[test]
+    fn frame_replaces_invalid_bytes() {
+        let mut pending = vec![b'a', 0xff, b'b'];
+        assert_eq!(take_frame(&mut pending).as_deref(), Some("a\u{fffd}b"));
+        assert!(pending.is_empty());
+    }
+}
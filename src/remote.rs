@@ -0,0 +1,325 @@
+/// The remote links the presenter view to the audience views of the same deck.
+/// `dough present --presenter` listens on a Unix socket, and every `dough present --follow` of the
+/// same project connects to it. The presenter sends the current slide, and forwards the keys it
+/// reads, so that the followers move through the deck in step with it.
+///
+/// The messages are lines of text:
+/// * `slide <n> <fragment>` - The presenter is on slide `n`, with its first `fragment` fragments revealed.
+///   It is sent when a follower connects, and when the slide or the fragment changes.
+/// * `key <key>` - A key pressed in the presenter view.
+///
+/// The socket lives in a directory only the user can enter, and a socket owned by another user is
+/// neither connected to nor replaced.
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use termion::event::Key;
+
+/// How often a follower tries to connect to the presenter, while it is not running.
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A message from the presenter.
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Slide(u32, usize),
+    Key(Key),
+}
+
+/// Returns true if the file belongs to the user running dough.
+fn owned(path: &Path) -> bool {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.uid() == unsafe { libc::getuid() },
+        Err(_) => false,
+    }
+}
+
+/// Returns the directory of the sockets: the runtime directory of the user, or a directory of the
+/// user in the temporary directory, which only the user can enter.
+fn socket_dir() -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        if dir.is_absolute() && dir.is_dir() && owned(&dir) {
+            return Ok(dir);
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("dough-{}", unsafe { libc::getuid() }));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(format!("Could not create {}: {}", dir.display(), err).into()),
+    }
+    // A directory made by another user, or one others can write to, could hold a socket that is not ours.
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || !owned(&dir) || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(format!("{} is not a private directory of the user", dir.display()).into());
+    }
+    Ok(dir)
+}
+
+/// Returns the socket of a project, in the runtime directory of the user.
+/// The name is derived from the path of the project, so the presenter and its followers find the
+/// same socket from any working directory.
+pub fn socket_path(project: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let project = project
+        .canonicalize()
+        .unwrap_or_else(|_| project.to_path_buf());
+    let mut hasher = DefaultHasher::new();
+    project.hash(&mut hasher);
+    Ok(socket_dir()?.join(format!("dough-{:016x}.sock", hasher.finish())))
+}
+
+/// The presenter side of the socket, which sends the messages to every connected follower.
+pub struct Broadcaster {
+    // The socket, removed when the presenter stops.
+    path: PathBuf,
+    followers: Arc<Mutex<Vec<UnixStream>>>,
+    // The current slide and fragment, sent to the followers as they connect.
+    slide: Arc<Mutex<(u32, usize)>>,
+}
+
+impl Broadcaster {
+    /// Listen on the socket of a project, and accept the followers in a separate thread.
+    /// A socket left behind by a presenter of the user that is no longer running is replaced.
+    /// # Arguments
+    /// * `path` - The socket to listen on.
+    /// # Returns
+    /// The broadcaster, or an error if another presenter is running for the project,
+    /// or if the socket belongs to another user.
+    pub fn listen(path: &Path) -> Result<Broadcaster, Box<dyn Error>> {
+        if std::fs::symlink_metadata(path).is_ok() {
+            if !owned(path) {
+                return Err(format!("{} belongs to another user", path.display()).into());
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!("A presenter is already running on {}", path.display()).into());
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)
+            .map_err(|err| format!("Could not listen on {}: {}", path.display(), err))?;
+
+        let broadcaster = Broadcaster {
+            path: path.to_path_buf(),
+            followers: Arc::new(Mutex::new(Vec::new())),
+            slide: Arc::new(Mutex::new((1, 0))),
+        };
+
+        let followers = broadcaster.followers.clone();
+        let slide = broadcaster.slide.clone();
+        thread::Builder::new()
+            .name("remote".to_string())
+            .spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    // The slide is sent while holding the lock, so that no change is missed
+                    let (slide, fragment) = *slide.lock().unwrap();
+                    if writeln!(stream, "slide {} {}", slide, fragment).is_ok() {
+                        followers.lock().unwrap().push(stream);
+                    }
+                }
+            })?;
+
+        Ok(broadcaster)
+    }
+
+    /// Send the current slide and fragment to the followers, if they changed.
+    pub fn set_slide(&self, slide: u32, fragment: usize) {
+        let mut current = self.slide.lock().unwrap();
+        if *current != (slide, fragment) {
+            *current = (slide, fragment);
+            self.send(&format!("slide {} {}", slide, fragment));
+        }
+    }
+
+    /// Forward a key to the followers.
+    pub fn send_key(&self, key: &Key) {
+        if let Some(key) = encode_key(key) {
+            self.send(&format!("key {}", key));
+        }
+    }
+
+    /// Send a message to every follower, and forget the followers that are gone.
+    fn send(&self, message: &str) {
+        self.followers
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| writeln!(stream, "{}", message).is_ok());
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Follow the presenter of a project in a separate thread.
+/// The follower connects to the socket as soon as the presenter listens on it, and connects again
+/// when the presenter is restarted. A socket of another user is not connected to.
+/// # Arguments
+/// * `path` - The socket of the project.
+/// * `on_message` - Called with every message from the presenter. The follower stops when it returns false.
+pub fn follow<F>(path: PathBuf, on_message: F) -> thread::JoinHandle<()>
+where
+    F: Fn(Message) -> bool + Send + 'static,
+{
+    thread::Builder::new()
+        .name("remote".to_string())
+        .spawn(move || loop {
+            let stream = match owned(&path).then(|| UnixStream::connect(&path)) {
+                Some(Ok(stream)) => stream,
+                _ => {
+                    thread::sleep(RETRY_INTERVAL);
+                    continue;
+                }
+            };
+            for line in BufReader::new(stream).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if let Some(message) = parse_message(&line) {
+                    if !on_message(message) {
+                        return;
+                    }
+                }
+            }
+            thread::sleep(RETRY_INTERVAL);
+        })
+        .expect("Failed to spawn thread")
+}
+
+/// Parses a line sent by the presenter, None if it is not a message.
+fn parse_message(line: &str) -> Option<Message> {
+    match line.split_once(' ') {
+        Some(("slide", position)) => {
+            let (slide, fragment) = position.trim().split_once(' ')?;
+            Some(Message::Slide(slide.parse().ok()?, fragment.parse().ok()?))
+        }
+        Some(("key", key)) => decode_key(key.trim()).map(Message::Key),
+        _ => None,
+    }
+}
+
+/// Encodes a key as a word, characters are sent as their code point.
+fn encode_key(key: &Key) -> Option<String> {
+    let key = match key {
+        Key::Char(c) => format!("char:{}", *c as u32),
+        Key::Ctrl(c) => format!("ctrl:{}", *c as u32),
+        Key::Alt(c) => format!("alt:{}", *c as u32),
+        Key::F(n) => format!("f:{}", n),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::BackTab => "backtab".to_string(),
+        Key::Esc => "esc".to_string(),
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn decode_key(key: &str) -> Option<Key> {
+    let char_of = |code: &str| code.parse::<u32>().ok().and_then(char::from_u32);
+    let key = match key.split_once(':') {
+        Some(("char", code)) => Key::Char(char_of(code)?),
+        Some(("ctrl", code)) => Key::Ctrl(char_of(code)?),
+        Some(("alt", code)) => Key::Alt(char_of(code)?),
+        Some(("f", n)) => Key::F(n.parse().ok()?),
+        _ => match key {
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "backspace" => Key::Backspace,
+            "delete" => Key::Delete,
+            "insert" => Key::Insert,
+            "backtab" => Key::BackTab,
+            "esc" => Key::Esc,
+            _ => return None,
+        },
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_sent_as_words() {
+        let keys = [
+            Key::Char('l'),
+            Key::Char(' '),
+            Key::Char('日'),
+            Key::Ctrl('r'),
+            Key::Alt('x'),
+            Key::F(5),
+            Key::PageDown,
+            Key::Esc,
+        ];
+        for key in keys {
+            let line = format!("key {}", encode_key(&key).unwrap());
+            assert!(!line[4..].contains(' '));
+            assert_eq!(parse_message(&line), Some(Message::Key(key)));
+        }
+        assert_eq!(encode_key(&Key::Null), None);
+    }
+
+    #[test]
+    fn slide_messages_are_parsed() {
+        assert_eq!(parse_message("slide 3 1"), Some(Message::Slide(3, 1)));
+        assert_eq!(parse_message("slide 3"), None);
+        assert_eq!(parse_message("slide x 1"), None);
+        assert_eq!(parse_message("key unknown"), None);
+        assert_eq!(parse_message("hello"), None);
+    }
+
+    #[test]
+    fn followers_receive_the_slide_and_the_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.sock");
+        let broadcaster = Broadcaster::listen(&path).unwrap();
+        broadcaster.set_slide(2, 1);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        follow(path.clone(), move |message| sender.send(message).is_ok());
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            Message::Slide(2, 1)
+        );
+
+        // The follower is registered once the slide is sent, the key may be sent before.
+        while broadcaster.followers.lock().unwrap().is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        broadcaster.send_key(&Key::Right);
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap(),
+            Message::Key(Key::Right)
+        );
+
+        assert!(Broadcaster::listen(&path).is_err());
+        drop(broadcaster);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,299 @@
+/// The server serves the HTML rendering of a deck on localhost.
+/// Every request to `/` renders the deck from the files on disk, and the browser listens on
+/// `/events` for server-sent events, which tell it to reload when a slide or the style changes.
+/// The other requests are served the images of the project directory, the slides link to them.
+/// The server only binds to the loopback interface, and only answers requests made to it by name,
+/// so that a page of another site cannot reach it through its own host name.
+use crate::watcher;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use paris::Logger;
+use tiny_http::{Header, Request, Response, Server};
+
+/// How often an idle event stream is pinged, to notice browsers that went away.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The senders of all the connected event streams.
+type Clients = Arc<Mutex<Vec<Sender<()>>>>;
+
+/// Returns true if a change to the file should reload the deck.
+fn triggers_reload(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("md") | Some("yml") | Some("yaml")
+    ) || content_type(path).is_some()
+}
+
+/// Decodes the percent-encoded bytes of the path of a URL, such as `%20` for a space.
+/// Returns None if the path is not valid UTF-8 once decoded.
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Returns true if the Host header of a request names the server itself.
+/// A page of another site that resolves its own host name to the loopback address sends that name instead.
+fn allowed_host(host: Option<&str>, port: u16) -> bool {
+    match host {
+        Some(host) => {
+            let host = host.to_ascii_lowercase();
+            host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port)
+        }
+        None => false,
+    }
+}
+
+/// Returns the image of the project directory a URL path asks for.
+/// Returns None if there is no such image, if the path leads out of the project directory,
+/// or if it goes through a hidden file or directory.
+fn project_file(dir: &Path, url_path: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from(percent_decode(url_path.trim_start_matches('/'))?);
+    // `..` starts with a dot too, so the path can only go down from the project directory.
+    let hidden = relative.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        Component::CurDir => false,
+        _ => true,
+    });
+    if hidden {
+        return None;
+    }
+    content_type(&relative)?;
+    let dir = dir.canonicalize().ok()?;
+    // The links are resolved, so that a link cannot leave the project directory or point to another kind of file.
+    let path = dir.join(relative).canonicalize().ok()?;
+    if path.starts_with(&dir) && path.is_file() && content_type(&path).is_some() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Returns the content type of an image served from the project directory, from its extension.
+/// Returns None if the file is not an image.
+fn content_type(path: &Path) -> Option<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => Some("image/png"),
+        Some("jpg") | Some("jpeg") => Some("image/jpeg"),
+        Some("gif") => Some("image/gif"),
+        Some("webp") => Some("image/webp"),
+        Some("bmp") => Some("image/bmp"),
+        Some("svg") => Some("image/svg+xml"),
+        Some("ico") => Some("image/x-icon"),
+        _ => None,
+    }
+}
+
+/// Streams reload events to a browser until it disconnects.
+/// The response is written by hand, so that every event is flushed as soon as it is sent.
+fn stream_events(request: Request, clients: &Clients) {
+    let (sender, receiver) = channel();
+    clients.lock().unwrap().push(sender);
+
+    let mut writer = request.into_writer();
+    let headers = "HTTP/1.1 200 OK\r\n\
+                   Content-Type: text/event-stream\r\n\
+                   Cache-Control: no-cache\r\n\
+                   Connection: close\r\n\r\n";
+    if writer.write_all(headers.as_bytes()).is_err() || writer.flush().is_err() {
+        return;
+    }
+
+    loop {
+        let event = match receiver.recv_timeout(KEEP_ALIVE_INTERVAL) {
+            Ok(()) => "data: reload\n\n",
+            Err(RecvTimeoutError::Timeout) => ": ping\n\n",
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if writer.write_all(event.as_bytes()).is_err() || writer.flush().is_err() {
+            return;
+        }
+    }
+}
+
+/// Serve a deck on localhost until the process is stopped.
+/// # Arguments
+/// * `dir` - The project directory, the images are served from.
+/// * `watched` - The files of the deck, watched for changes.
+/// * `port` - The port to listen on.
+/// * `render` - Renders the HTML page of the deck.
+pub fn serve<F>(
+    dir: PathBuf,
+    watched: watcher::Watched,
+    port: u16,
+    render: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: Fn() -> Result<String, Box<dyn Error>> + Send + Sync + 'static,
+{
+    let mut log = Logger::new();
+    let server = Server::http(("127.0.0.1", port)).map_err(|err| err.to_string())?;
+    let render = Arc::new(render);
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+
+    // Every change to a slide, an image or the style reloads all the connected browsers.
+    let watched_clients = clients.clone();
+    watcher::watch(watched, move |path| {
+        if triggers_reload(path) {
+            watched_clients
+                .lock()
+                .unwrap()
+                .retain(|client| client.send(()).is_ok());
+        }
+        true
+    });
+
+    log.success(format!("Serving the deck on http://127.0.0.1:{}", port));
+    log.info("Press Ctrl+C to stop the server");
+
+    for request in server.incoming_requests() {
+        let render = render.clone();
+        let clients = clients.clone();
+        let dir = dir.clone();
+
+        // The event streams are long lived, so every request is handled in its own thread.
+        thread::spawn(move || {
+            // The query string, such as the one a browser adds to bypass its cache, is not part of the route.
+            let url = request.url().to_string();
+            let path = url.split(['?', '#']).next().unwrap_or("/");
+            let host = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Host"))
+                .map(|header| header.value.as_str());
+            if !allowed_host(host, port) {
+                let _ = request.respond(Response::from_string("Forbidden").with_status_code(403));
+                return;
+            }
+            let response = match path {
+                "/events" => return stream_events(request, &clients),
+                "/" | "/index.html" => match render() {
+                    Ok(page) => Response::from_string(page)
+                        .with_header(
+                            Header::from_bytes(
+                                &b"Content-Type"[..],
+                                &b"text/html; charset=utf-8"[..],
+                            )
+                            .unwrap(),
+                        )
+                        .boxed(),
+                    Err(err) => {
+                        Response::from_string(format!("Could not render the deck: {}", err))
+                            .with_status_code(500)
+                            .boxed()
+                    }
+                },
+                // The images of the slides are linked relative to the project directory.
+                path => {
+                    let file = project_file(&dir, path)
+                        .and_then(|file| File::open(&file).ok().map(|opened| (file, opened)));
+                    match file {
+                        Some((file, opened)) => Response::from_file(opened)
+                            .with_header(
+                                Header::from_bytes(
+                                    &b"Content-Type"[..],
+                                    content_type(&file).unwrap_or_default().as_bytes(),
+                                )
+                                .unwrap(),
+                            )
+                            .boxed(),
+                        None => Response::from_string("Not found")
+                            .with_status_code(404)
+                            .boxed(),
+                    }
+                }
+            };
+            let _ = request.respond(response);
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_decodes_escapes() {
+        assert_eq!(
+            percent_decode("my%20image.png").as_deref(),
+            Some("my image.png")
+        );
+        assert_eq!(percent_decode("%E6%97%A5.png").as_deref(), Some("日.png"));
+        // An incomplete escape is kept as it is.
+        assert_eq!(percent_decode("100%").as_deref(), Some("100%"));
+        assert_eq!(percent_decode("%zz").as_deref(), Some("%zz"));
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn project_file_serves_images_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("img")).unwrap();
+        std::fs::write(dir.path().join("img/a b.png"), "").unwrap();
+
+        let file = project_file(dir.path(), "/img/a%20b.png").unwrap();
+        assert!(file.ends_with("img/a b.png"));
+        assert_eq!(content_type(&file), Some("image/png"));
+        assert_eq!(project_file(dir.path(), "/img/missing.png"), None);
+    }
+
+    #[test]
+    fn project_file_rejects_paths_out_of_the_project() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("deck");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(root.path().join("secret.png"), "").unwrap();
+        std::fs::write(dir.join(".hidden.png"), "").unwrap();
+        std::fs::create_dir(dir.join(".git")).unwrap();
+        std::fs::write(dir.join(".git/a.png"), "").unwrap();
+        std::fs::write(dir.join("01.md"), "").unwrap();
+        std::os::unix::fs::symlink(root.path().join("secret.png"), dir.join("link.png")).unwrap();
+
+        assert_eq!(project_file(&dir, "/../secret.png"), None);
+        assert_eq!(project_file(&dir, "/%2e%2e/secret.png"), None);
+        assert_eq!(project_file(&dir, "/link.png"), None);
+        assert_eq!(project_file(&dir, "/.hidden.png"), None);
+        assert_eq!(project_file(&dir, "/.git/a.png"), None);
+        assert_eq!(project_file(&dir, "/01.md"), None);
+    }
+
+    #[test]
+    fn allowed_host_only_accepts_the_server_itself() {
+        assert!(allowed_host(Some("127.0.0.1:8000"), 8000));
+        assert!(allowed_host(Some("LocalHost:8000"), 8000));
+        assert!(!allowed_host(Some("localhost:8001"), 8000));
+        assert!(!allowed_host(Some("attacker.example:8000"), 8000));
+        assert!(!allowed_host(None, 8000));
+    }
+}
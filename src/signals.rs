@@ -0,0 +1,97 @@
+/// The signal watcher tells the presenter when the terminal is resized, and when it is suspended.
+/// The signal handlers only write the signal to a pipe, a thread reads the pipe and calls back, so
+/// that the presenter is never called from a signal handler.
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+
+/// The end of the pipe the signal handlers write to, -1 until the watcher starts.
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// A signal the presenter reacts to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGWINCH, the terminal was resized.
+    Resize,
+    /// SIGTSTP, the presenter is asked to stop, such as by `kill -TSTP`.
+    Suspend,
+}
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    let fd = PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        // The write end does not block, a full pipe already holds signals that are not read yet.
+        let byte = signal as u8;
+        unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+    }
+}
+
+/// Install the handler of a signal.
+/// SA_RESTART keeps the read of the keys going when the signal arrives.
+fn handle(signal: libc::c_int) -> io::Result<()> {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    action.sa_flags = libc::SA_RESTART;
+    unsafe { libc::sigemptyset(&mut action.sa_mask) };
+    if unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Watch for resizes of the terminal, and for requests to suspend the presenter, in a separate thread.
+/// # Arguments
+/// * `on_signal` - Called with every signal received. The watcher stops when it returns false.
+/// # Returns
+/// The thread of the watcher, or the error installing the signal handlers.
+pub fn watch<F>(on_signal: F) -> io::Result<thread::JoinHandle<()>>
+where
+    F: Fn(Signal) -> bool + Send + 'static,
+{
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (read_end, write_end) = (fds[0], fds[1]);
+    unsafe {
+        let flags = libc::fcntl(write_end, libc::F_GETFL);
+        libc::fcntl(write_end, libc::F_SETFL, flags | libc::O_NONBLOCK);
+    }
+    PIPE.store(write_end, Ordering::Relaxed);
+    handle(libc::SIGWINCH)?;
+    handle(libc::SIGTSTP)?;
+
+    thread::Builder::new()
+        .name("signals".to_string())
+        .spawn(move || {
+            let mut buffer = [0u8; 64];
+            loop {
+                let read = unsafe {
+                    libc::read(
+                        read_end,
+                        buffer.as_mut_ptr() as *mut libc::c_void,
+                        buffer.len(),
+                    )
+                };
+                if read < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                if read <= 0 {
+                    break;
+                }
+
+                // The resizes of a window being dragged are read together, and reported once.
+                let received = &buffer[..read as usize];
+                let mut signals = Vec::new();
+                if received.contains(&(libc::SIGTSTP as u8)) {
+                    signals.push(Signal::Suspend);
+                }
+                if received.contains(&(libc::SIGWINCH as u8)) {
+                    signals.push(Signal::Resize);
+                }
+                if !signals.into_iter().all(&on_signal) {
+                    break;
+                }
+            }
+        })
+}
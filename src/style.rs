@@ -0,0 +1,390 @@
+/// The style of a deck, read from the style.yml file of the project.
+/// Every key is optional and falls back to a default, unknown keys and invalid values are errors.
+use crate::graphics::Protocol;
+use crate::keymap::KeyBindings;
+use crate::prettify;
+use crate::ramen::{self, Runner};
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+/// The default style.yml file, written to projects that do not have one.
+pub const DEFAULT_STYLE: &str = include_str!("../templates/default/style.yml");
+
+/// A terminal color, such as `red`, `bright blue` or `#ff8800`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color(pub colored::Color);
+
+impl From<Color> for colored::Color {
+    fn from(color: Color) -> Self {
+        color.0
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color(colored::Color::TrueColor { r, g, b })),
+                _ => Err(format!("invalid hex color `{}`", value)),
+            };
+        }
+        value
+            .parse::<colored::Color>()
+            .map(Color)
+            .map_err(|_| format!("unknown color `{}`", value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+/// A foreground and a background color, written as `black on white`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorPair {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl ColorPair {
+    fn new(foreground: colored::Color, background: colored::Color) -> ColorPair {
+        ColorPair {
+            foreground: Color(foreground),
+            background: Color(background),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorPair {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let (foreground, background) = value.split_once(" on ").ok_or_else(|| {
+            de::Error::custom(format!(
+                "expected `<foreground> on <background>`, found `{}`",
+                value
+            ))
+        })?;
+        Ok(ColorPair {
+            foreground: foreground.parse().map_err(de::Error::custom)?,
+            background: background.parse().map_err(de::Error::custom)?,
+        })
+    }
+}
+
+/// The style of a deck.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Style {
+    /// The colors of the highlighted line.
+    pub highlighter: ColorPair,
+
+    // Markdown styles
+    pub h1: Color,
+    pub h2: Color,
+    pub h3: Color,
+    pub h4: Color,
+    pub h5: Color,
+    pub h6: Color,
+    pub blockquote: ColorPair,
+    pub ordered_list_bullet: Color,
+    pub unordered_list_bullet: Color,
+    pub ordered_list: Color,
+    pub unordered_list: Color,
+    pub link_text: Color,
+    pub link_url: Color,
+    pub definition: Color,
+    pub inline_code: Color,
+    pub table_header: Color,
+    pub table_border: Color,
+
+    // Terminal styles
+    /// Reveal the blocks of every slide one at a time.
+    pub clear: bool,
+    /// Draw a box around the slide.
+    #[serde(rename = "box")]
+    pub boxed: bool,
+    pub box_color: ColorPair,
+    pub vertical_alignment: bool,
+    pub horizontal_alignment: bool,
+    /// The number of columns the content of a slide may take, longer lines are wrapped. 0 uses the width of the terminal.
+    pub max_width: usize,
+    pub syntax_highlighting: bool,
+    pub syntax_theme: String,
+    pub syntax_bg: bool,
+    /// The line that splits a single-file deck into slides.
+    pub slide_separator: String,
+    /// Show the progress of the presentation in the bottom left corner.
+    pub progress: bool,
+    /// How images are drawn: auto, kitty, iterm, sixel or blocks.
+    pub image_protocol: Protocol,
+    /// The seconds a code block can run for before it is stopped.
+    pub code_timeout: f64,
+    /// The seconds a code block can compile for before it is stopped, such as a Rust block building its dependencies.
+    pub compile_timeout: f64,
+    /// The memory a code block can allocate, in megabytes. 0 disables the limit.
+    pub code_memory_limit: u64,
+    /// The edition Rust code blocks are compiled with.
+    #[serde(deserialize_with = "deserialize_edition")]
+    pub rust_edition: String,
+
+    /// The runners used to run the code blocks, by language.
+    /// Languages missing from the style.yml file keep their default runner.
+    #[serde(deserialize_with = "deserialize_runtime_map")]
+    pub runtime_map: BTreeMap<String, Runner>,
+
+    /// The keys bound to the actions of the presenter.
+    /// Actions missing from the style.yml file keep their default keys.
+    pub keys: KeyBindings,
+}
+
+/// An edition is a year, which YAML reads as a number unless it is quoted.
+fn deserialize_edition<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_yaml::Value::deserialize(deserializer)? {
+        serde_yaml::Value::String(edition) => Ok(edition),
+        serde_yaml::Value::Number(edition) => Ok(edition.to_string()),
+        _ => Err(de::Error::custom("expected an edition, such as 2021")),
+    }
+}
+
+fn deserialize_runtime_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Runner>, D::Error> {
+    let runtime_map = BTreeMap::<String, serde_yaml::Value>::deserialize(deserializer)?;
+    ramen::parse_runners(runtime_map).map_err(de::Error::custom)
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        use colored::Color::*;
+
+        Style {
+            highlighter: ColorPair::new(Black, White),
+            h1: Color(Red),
+            h2: Color(Yellow),
+            h3: Color(Green),
+            h4: Color(Blue),
+            h5: Color(Magenta),
+            h6: Color(Cyan),
+            blockquote: ColorPair::new(Black, White),
+            ordered_list_bullet: Color(Green),
+            unordered_list_bullet: Color(Green),
+            ordered_list: Color(Blue),
+            unordered_list: Color(Blue),
+            link_text: Color(Blue),
+            link_url: Color(Green),
+            definition: Color(Green),
+            inline_code: Color(Red),
+            table_header: Color(Cyan),
+            table_border: Color(White),
+            clear: false,
+            boxed: true,
+            box_color: ColorPair::new(Black, White),
+            vertical_alignment: true,
+            horizontal_alignment: true,
+            max_width: 0,
+            syntax_highlighting: true,
+            syntax_theme: "base16-ocean.dark".to_string(),
+            syntax_bg: false,
+            slide_separator: crate::deck::DEFAULT_SEPARATOR.to_string(),
+            progress: false,
+            image_protocol: Protocol::Auto,
+            code_timeout: 10.0,
+            compile_timeout: 300.0,
+            code_memory_limit: 1024,
+            rust_edition: "2021".to_string(),
+            runtime_map: ramen::default_runners(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+/// The error returned when a style.yml file cannot be used.
+#[derive(Debug)]
+pub struct StyleError(String);
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for StyleError {}
+
+/// Describe an error in a style, along with the key it was found in.
+/// The errors of invalid colors do not say which key they belong to, so every key is parsed on
+/// its own until the invalid one is found.
+fn locate_error(value: &serde_yaml::Value, err: serde_yaml::Error) -> String {
+    if let serde_yaml::Value::Mapping(mapping) = value {
+        for (key, entry) in mapping {
+            let mut single = serde_yaml::Mapping::new();
+            single.insert(key.clone(), entry.clone());
+            if let Err(err) = serde_yaml::from_value::<Style>(serde_yaml::Value::Mapping(single)) {
+                return match key.as_str() {
+                    Some(key) => format!("{}: {}", key, err),
+                    None => err.to_string(),
+                };
+            }
+        }
+    }
+    err.to_string()
+}
+
+impl Style {
+    /// Parse a style from the contents of a style.yml file.
+    pub fn parse(text: &str) -> Result<Style, StyleError> {
+        // Older style files declare the runtime map as a list item (`- runtime_map:`), which
+        // is not valid YAML next to the other keys. It is read as a regular key instead.
+        let legacy_runtime_map = Regex::new(r"(?m)^(\s*)-\s*runtime_map:").unwrap();
+        let text = legacy_runtime_map.replace_all(text, "${1}runtime_map:");
+
+        // An empty file is a valid style, with every key set to its default.
+        if text.lines().all(|line| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with('#')
+        }) {
+            return Ok(Style::default());
+        }
+
+        let value: serde_yaml::Value =
+            serde_yaml::from_str(&text).map_err(|err| StyleError(err.to_string()))?;
+        let style: Style = serde_yaml::from_value(value.clone())
+            .map_err(|err| StyleError(locate_error(&value, err)))?;
+
+        for (key, seconds) in [
+            ("code_timeout", style.code_timeout),
+            ("compile_timeout", style.compile_timeout),
+        ] {
+            if !(seconds.is_finite() && seconds > 0.0) {
+                return Err(StyleError(format!(
+                    "{}: expected a positive number of seconds, found {}",
+                    key, seconds
+                )));
+            }
+        }
+        // The limit is set in bytes, which must fit in 64 bits.
+        if style.code_memory_limit.checked_mul(1024 * 1024).is_none() {
+            return Err(StyleError(format!(
+                "code_memory_limit: expected a number of megabytes up to {}, found {}",
+                u64::MAX / (1024 * 1024),
+                style.code_memory_limit
+            )));
+        }
+        if !prettify::has_theme(&style.syntax_theme) {
+            return Err(StyleError(format!(
+                "syntax_theme: unknown theme `{}`, expected one of {}",
+                style.syntax_theme,
+                prettify::theme_names().join(", ")
+            )));
+        }
+        Ok(style)
+    }
+
+    /// Read the style from a style.yml file.
+    pub fn load(path: &Path) -> Result<Style, StyleError> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| StyleError(format!("Could not read {}: {}", path.display(), err)))?;
+        Style::parse(&text)
+            .map_err(|err| StyleError(format!("Invalid {}: {}", path.display(), err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> String {
+        Style::parse(text).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn empty_style_is_default() {
+        let style = Style::parse("# Nothing but a comment\n\n").unwrap();
+        assert_eq!(style.h1, Style::default().h1);
+        assert_eq!(style.code_timeout, 10.0);
+    }
+
+    #[test]
+    fn default_style_file_parses() {
+        assert!(Style::parse(DEFAULT_STYLE).is_ok());
+    }
+
+    #[test]
+    fn colors_and_edition() {
+        let style = Style::parse("h1: bright blue\nh2: \"#ff8800\"\nrust_edition: 2018\n").unwrap();
+        assert_eq!(style.h1, Color(colored::Color::BrightBlue));
+        assert_eq!(
+            style.h2,
+            Color(colored::Color::TrueColor {
+                r: 0xff,
+                g: 0x88,
+                b: 0x00
+            })
+        );
+        assert_eq!(style.rust_edition, "2018");
+    }
+
+    #[test]
+    fn unknown_key() {
+        let err = parse_error("h1: red\nheading_color: blue\n");
+        assert!(err.starts_with("heading_color: unknown field"), "{}", err);
+    }
+
+    #[test]
+    fn wrong_type() {
+        let err = parse_error("h1: red\nmax_width: wide\n");
+        assert!(err.starts_with("max_width: "), "{}", err);
+    }
+
+    #[test]
+    fn invalid_color_is_located() {
+        let err = parse_error("h1: red\nh3: purplish\n");
+        assert!(err.starts_with("h3: "), "{}", err);
+        assert!(err.contains("unknown color `purplish`"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_timeout() {
+        let err = parse_error("code_timeout: 0\n");
+        assert!(
+            err.starts_with("code_timeout: expected a positive number of seconds"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn memory_limit_too_large() {
+        let err = parse_error("code_memory_limit: 18446744073709551615\n");
+        assert!(
+            err.starts_with("code_memory_limit: expected a number of megabytes"),
+            "{}",
+            err
+        );
+        assert!(Style::parse("code_memory_limit: 0\n").is_ok());
+    }
+
+    #[test]
+    fn legacy_runtime_map_list_form() {
+        let style = Style::parse("h1: red\n- runtime_map:\n    lua: lua5.4\n").unwrap();
+        assert_eq!(style.runtime_map["lua"].run, "lua5.4 {file}");
+        assert!(style.runtime_map.contains_key("python"));
+    }
+}
@@ -0,0 +1,174 @@
+/// The SVG backend draws a slide as an SVG image, as the terminal shows it.
+/// The image is a grid of cells in a monospace font: the text keeps the colors the renderer gives
+/// it, and the box-drawing characters, block elements and checkboxes are drawn as shapes, so that
+/// the borders line up whatever font the viewer has.
+use crate::grid::{Attributes, Canvas, Grid, Rgb, DEFAULT_BACKGROUND};
+use crate::utils::escape_html;
+
+use std::fmt::Write;
+
+/// The size of the text, in pixels.
+const FONT_SIZE: f32 = 14.0;
+/// The size of a cell of the grid, in pixels. A character of a monospace font is about 0.6 times as wide as its size.
+const CELL_WIDTH: f32 = 8.4;
+const CELL_HEIGHT: f32 = 18.0;
+/// The space around the slide, in pixels.
+const MARGIN: f32 = 16.0;
+/// The height of the baseline of the text above the bottom of its cell, in pixels.
+const BASELINE: f32 = 4.5;
+
+/// The fonts of the image, the first one the viewer has is used.
+const FONT_FAMILY: &str =
+    "ui-monospace, SFMono-Regular, Menlo, Consolas, 'DejaVu Sans Mono', monospace";
+
+/// Draw the grid of a slide as an SVG image, with the background of the terminal around it.
+/// # Arguments
+/// * `grid` - The grid of the slide.
+/// # Returns
+/// The SVG image.
+pub fn write_slide(grid: &Grid) -> String {
+    let width = grid.cols as f32 * CELL_WIDTH + 2.0 * MARGIN;
+    let height = grid.rows as f32 * CELL_HEIGHT + 2.0 * MARGIN;
+
+    let mut image = Image {
+        content: String::new(),
+    };
+    let _ = writeln!(
+        image.content,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="{}" font-size="{}" xml:space="preserve">"#,
+        FONT_FAMILY,
+        number(FONT_SIZE),
+        w = number(width),
+        h = number(height),
+    );
+    image.fill(DEFAULT_BACKGROUND, -MARGIN, -MARGIN, width, height);
+    grid.draw(&mut image, CELL_WIDTH, CELL_HEIGHT);
+    image.content.push_str("</svg>\n");
+    image.content
+}
+
+/// The elements of an image. The positions of the grid are moved within the margins.
+struct Image {
+    content: String,
+}
+
+impl Canvas for Image {
+    fn fill(&mut self, rgb: Rgb, x: f32, y: f32, width: f32, height: f32) {
+        let _ = writeln!(
+            self.content,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            number(MARGIN + x),
+            number(MARGIN + y),
+            number(width),
+            number(height),
+            color(rgb)
+        );
+    }
+
+    fn text(&mut self, text: &str, attributes: &Attributes, x: f32, y: f32, width: f32) {
+        let (foreground, _) = attributes.colors();
+        let mut font = String::new();
+        if attributes.bold {
+            font.push_str(r#" font-weight="bold""#);
+        }
+        if attributes.italic {
+            font.push_str(r#" font-style="italic""#);
+        }
+        // The text is stretched to its cells, so that a font wider or narrower than the grid keeps the columns.
+        let _ = writeln!(
+            self.content,
+            r#"<text x="{}" y="{}" textLength="{}" lengthAdjust="spacingAndGlyphs" fill="{}"{}>{}</text>"#,
+            number(MARGIN + x),
+            number(MARGIN + y + CELL_HEIGHT - BASELINE),
+            number(width),
+            color(foreground),
+            font,
+            escape_html(text)
+        );
+    }
+
+    fn checkbox(&mut self, rgb: Rgb, x: f32, y: f32, size: f32, checked: bool) {
+        let (x, y) = (MARGIN + x, MARGIN + y);
+        let _ = writeln!(
+            self.content,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+            number(x),
+            number(y),
+            number(size),
+            number(size),
+            color(rgb)
+        );
+        if checked {
+            let _ = writeln!(
+                self.content,
+                r#"<polyline points="{},{} {},{} {},{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+                number(x + size * 0.2),
+                number(y + size * 0.5),
+                number(x + size * 0.4),
+                number(y + size * 0.8),
+                number(x + size * 0.85),
+                number(y + size * 0.15),
+                color(rgb)
+            );
+        }
+    }
+}
+
+/// Returns a color as an SVG color.
+fn color(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2)
+}
+
+/// Returns a number as it is written in SVG, with at most two decimals.
+fn number(value: f32) -> String {
+    let value = format!("{:.2}", value);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    match value {
+        "" | "-0" => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_size_follows_grid() {
+        let image = write_slide(&Grid::parse("One", 10, 2));
+        assert!(image.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="116" height="68" viewBox="0 0 116 68""#));
+        assert!(image.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let image = write_slide(&Grid::parse("<a & \"b\">", 10, 1));
+        assert!(
+            image.contains(">&lt;a &amp; &quot;b&quot;&gt;</text>"),
+            "{}",
+            image
+        );
+    }
+
+    #[test]
+    fn wide_text_keeps_its_cells() {
+        let image = write_slide(&Grid::parse("日本", 10, 1));
+        assert!(
+            image.contains(r#"<text x="16" y="29.5" textLength="16.8""#),
+            "{}",
+            image
+        );
+        assert!(
+            image.contains(r#"<text x="32.8" y="29.5" textLength="16.8""#),
+            "{}",
+            image
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(8.4), "8.4");
+        assert_eq!(number(1.0 / 3.0), "0.33");
+        assert_eq!(number(-0.001), "0");
+    }
+}
@@ -0,0 +1,149 @@
+/// The terminal guard runs the presenter on the alternate screen of the terminal, in raw mode.
+/// The terminal is given back as it was found, with the cursor shown and the main screen and its
+/// scrollback intact, when the guard is dropped: when the presentation ends, when it fails, and when
+/// it panics. A panic in another thread ends the presenter, which gives the terminal back before
+/// the panic is reported, rather than leaving it drawing on a terminal that was given back.
+/// The presenter can be suspended like any other program, the terminal is given back
+/// while it is stopped and taken again when it continues.
+use std::io::{self, stdout, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// The attributes of the terminal before it was taken, None when no guard is alive
+    static ref ORIGINAL: Mutex<Option<libc::termios>> = Mutex::new(None);
+    /// The thread the presenter runs on, the thread that took the terminal
+    static ref PRESENTER: Mutex<Option<ThreadId>> = Mutex::new(None);
+    /// Ends the presenter with the message of a panic in another thread
+    static ref PANIC_HANDLER: Mutex<Option<PanicHandler>> = Mutex::new(None);
+}
+
+/// Called with the message of a panic in another thread than the presenter.
+type PanicHandler = Box<dyn Fn(String) + Send>;
+
+/// Whether the terminal is taken, so that it is given back once, and not again by the guard after a panic.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// The panic hook is installed by the first guard, and kept for the next ones.
+static PANIC_HOOK: Once = Once::new();
+
+/// Holds the terminal while the presenter runs, and gives it back when dropped.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    /// Take the terminal: switch to the alternate screen, hide the cursor and enter raw mode.
+    /// # Returns
+    /// The guard, or an error if the output is not a terminal.
+    pub fn new() -> io::Result<TerminalGuard> {
+        let mut attributes: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDOUT_FILENO, &mut attributes) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        *ORIGINAL.lock().unwrap() = Some(attributes);
+        *PRESENTER.lock().unwrap() = Some(thread::current().id());
+
+        PANIC_HOOK.call_once(|| {
+            let default_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                // A panic in another thread is handed to the presenter, which ends and gives the terminal back.
+                let presenter = *PRESENTER.lock().unwrap_or_else(|e| e.into_inner());
+                if TAKEN.load(Ordering::SeqCst) && presenter != Some(thread::current().id()) {
+                    let handler = PANIC_HANDLER.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(handler) = handler.as_ref() {
+                        handler(info.to_string());
+                        return;
+                    }
+                }
+                // The panic is printed on the main screen, once the terminal is given back.
+                // Giving the terminal back twice does nothing, so the guard of the presenter can still drop.
+                restore();
+                default_hook(info);
+            }));
+        });
+
+        enter()?;
+        Ok(TerminalGuard { _private: () })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+        *ORIGINAL.lock().unwrap() = None;
+        *PRESENTER.lock().unwrap() = None;
+        *PANIC_HANDLER.lock().unwrap() = None;
+    }
+}
+
+/// Set how the presenter is ended when another thread panics while the terminal is taken.
+/// Without a handler, the terminal is given back and the panic is printed right away.
+/// # Arguments
+/// * `handler` - Called with the message of the panic, in the thread that panicked.
+pub fn on_panic<F>(handler: F)
+where
+    F: Fn(String) + Send + 'static,
+{
+    *PANIC_HANDLER.lock().unwrap() = Some(Box::new(handler));
+}
+
+/// Switch to the alternate screen, hide the cursor and enter raw mode.
+/// The output is still processed in raw mode, so that the new lines of the slides return to the first column.
+fn enter() -> io::Result<()> {
+    let original = match *ORIGINAL.lock().unwrap() {
+        Some(original) => original,
+        None => return Ok(()),
+    };
+    let mut raw = original;
+    unsafe { libc::cfmakeraw(&mut raw) };
+    raw.c_oflag = original.c_oflag;
+    if unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSADRAIN, &raw) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    TAKEN.store(true, Ordering::SeqCst);
+
+    let mut stdout = stdout();
+    write!(
+        stdout,
+        "{}{}{}",
+        termion::screen::ToAlternateScreen,
+        termion::cursor::Hide,
+        termion::clear::All
+    )?;
+    stdout.flush()
+}
+
+/// Give the terminal back: show the cursor, switch to the main screen and leave raw mode.
+/// Giving back a terminal that is not taken does nothing.
+fn restore() {
+    if !TAKEN.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let original = match *ORIGINAL.lock().unwrap_or_else(|e| e.into_inner()) {
+        Some(original) => original,
+        None => return,
+    };
+    let mut stdout = stdout();
+    let _ = write!(
+        stdout,
+        "{}{}",
+        termion::cursor::Show,
+        termion::screen::ToMainScreen
+    );
+    let _ = stdout.flush();
+    unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSADRAIN, &original) };
+}
+
+/// Suspend the presenter: give the terminal back, stop until the shell continues it with `fg`,
+/// and take the terminal again. The screen is blank when the presenter continues, it is drawn again.
+pub fn suspend() -> io::Result<()> {
+    restore();
+    unsafe { libc::raise(libc::SIGSTOP) };
+    enter()
+}
@@ -1,6 +1,15 @@
+use lazy_static::lazy_static;
 use paris::Logger;
 use regex::Regex;
 use std::collections::HashMap;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+lazy_static! {
+    /// Matches a `<!-- pause -->` marker, which starts a new fragment of a slide.
+    pub static ref PAUSE: Regex = Regex::new(r"^<!--\s*pause\s*-->$").unwrap();
+    /// Matches the alignment flags, `$[c]$` for a line, and `$[c]` ... `$[e]` for a block.
+    pub static ref ALIGNMENT_FLAG: Regex = Regex::new(r"\$\[[clre]\]\$?").unwrap();
+}
 
 pub fn calculate_length_of_line(line: &str, spaces: bool) -> usize {
     let leading_spaces = strip_ansi_codes(line)
@@ -93,6 +102,32 @@ pub fn strip_ansi_codes(line: &str) -> String {
     result.to_string()
 }
 
+/// Replaces the text of a rendered block with blank space of the same width.
+/// The block keeps its place in the slide, and the alignment flags are kept, so that the visible
+/// blocks are laid out the same way whether it is shown or not.
+pub fn conceal(text: &str) -> String {
+    text.split('\n')
+        .map(|original| {
+            let line = strip_ansi_codes(original);
+            let line = line.as_str();
+            let mut concealed = String::new();
+            let mut last = 0;
+            for flag in ALIGNMENT_FLAG.find_iter(line) {
+                concealed.push_str(&" ".repeat(visible_width(&line[last..flag.start()])));
+                concealed.push_str(flag.as_str());
+                last = flag.end();
+            }
+            concealed.push_str(&" ".repeat(visible_width(&line[last..])));
+            // A line holding only escape codes is kept, the layout drops trailing empty lines
+            if concealed.is_empty() && !original.is_empty() {
+                concealed.push_str("\x1b[0m");
+            }
+            concealed
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 pub fn remove_last_n_lines(text: &str, n: u32) -> String {
     let mut lines: Vec<&str> = text.lines().collect();
 
@@ -107,9 +142,17 @@ pub fn remove_last_n_lines(text: &str, n: u32) -> String {
     return lines.join("\n");
 }
 
+/// Removes the comments from the markdown text.
+/// The `<!-- pause -->` markers are kept, they split the slide into fragments.
 pub fn remove_comments(text: &str) -> String {
     let re = Regex::new(r"(?s)<!--.*?-->").unwrap();
-    let result = re.replace_all(text, "");
+    let result = re.replace_all(text, |captures: &regex::Captures| {
+        if PAUSE.is_match(&captures[0]) {
+            captures[0].to_string()
+        } else {
+            String::new()
+        }
+    });
     result.to_string()
 }
 
@@ -119,69 +162,7 @@ pub fn create_style(project: std::path::PathBuf) -> Result<(), Box<dyn std::erro
     let verify_path = style_path.clone();
 
     if !style_path.exists() {
-        std::fs::write(style_path, "
-        # This file contains the default style settings for the terminal markdown renderer.
-
-        # The highlighter
-        highlighter: black on white
-        
-        # Markdown styles
-        h1: red
-        h2: yellow
-        h3: green
-        h4: cyan
-        h5: blue
-        h6: purple
-        blockquote: white on black
-        ordered_list_bullet: yellow
-        unordered_list_bullet: yellow
-        ordered_list: white
-        unordered_list: white
-        link_text: black
-        link_url: blue
-        definition: yellow
-        
-        # Terminal styles
-        
-        # clear will clear the terminal before rendering, you would need to scroll down to render each line
-        clear: false
-        
-        box: true
-        box_color: black on white
-        
-        # vertical_alignment will vertically align the text to the middle of the terminal
-        vertical_alignment: true
-        
-        # horizontal_alignment will horizontally align the text to the middle of the terminal
-        horizontal_alignment: true
-        
-        # syntax_highlighting will highlight the code syntax
-        # this works well with the warp terminal, but not with the default Mac OS terminal
-        
-        syntax_highlighting: true
-        syntax_theme: base16-ocean.dark
-        #themes:[base16-ocean.dark,base16-eighties.dark,base16-mocha.dark,base16-ocean.light, Solarized (dark) and Solarized (light)]
-        syntax_bg: false
-        
-        # shows the progress of the presentation: i [1/10]  : current slide/total slides in the bottom left corner
-        progress: false
-        
-        # runtime map is used to store the runtimes for different languages
-        # you can add your own runtimes for different languages. Currently, the following runtimes are supported:
-        
-        - runtime_map:
-            python: python3
-            javascript: node
-            ruby: ruby
-            c: gcc
-            cpp: g++
-            java: java
-            go: go run
-            rust: cargo run
-            swift: swift
-            kotlin: kotlinc
-            typescript: tsc
-")?;
+        std::fs::write(style_path, crate::style::DEFAULT_STYLE)?;
         if verify_path.exists() {
             log.info("fin style.yml");
             Ok(())
@@ -206,3 +187,245 @@ pub fn check_if_text_is_right_aligned(prettified: &str) -> bool {
 
     is_line_right_aligned || is_block_right_aligned
 }
+
+/// Escapes the characters that have a special meaning in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts a color from the style into a CSS color.
+/// The terminal color names are mapped to the xterm palette.
+pub fn css_color(color: crate::style::Color) -> String {
+    let hex = match color.0 {
+        colored::Color::Black => "#000000",
+        colored::Color::Red => "#cd0000",
+        colored::Color::Green => "#00cd00",
+        colored::Color::Yellow => "#cdcd00",
+        colored::Color::Blue => "#0000ee",
+        colored::Color::Magenta => "#cd00cd",
+        colored::Color::Cyan => "#00cdcd",
+        colored::Color::White => "#e5e5e5",
+        colored::Color::BrightBlack => "#7f7f7f",
+        colored::Color::BrightRed => "#ff0000",
+        colored::Color::BrightGreen => "#00ff00",
+        colored::Color::BrightYellow => "#ffff00",
+        colored::Color::BrightBlue => "#5c5cff",
+        colored::Color::BrightMagenta => "#ff00ff",
+        colored::Color::BrightCyan => "#00ffff",
+        colored::Color::BrightWhite => "#ffffff",
+        colored::Color::TrueColor { r, g, b } => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+    };
+    hex.to_string()
+}
+
+/// A piece of a line that is being wrapped: an ANSI color code, which takes no room, or a character.
+enum Token<'a> {
+    Code(&'a str),
+    Char(char),
+}
+
+/// Splits a line into color codes and characters.
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let re = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    let mut tokens = Vec::new();
+    let mut last = 0;
+    for code in re.find_iter(line) {
+        tokens.extend(line[last..code.start()].chars().map(Token::Char));
+        tokens.push(Token::Code(code.as_str()));
+        last = code.end();
+    }
+    tokens.extend(line[last..].chars().map(Token::Char));
+    tokens
+}
+
+/// Returns the number of columns a line takes in the terminal, without its color codes.
+pub fn visible_width(line: &str) -> usize {
+    UnicodeWidthStr::width(strip_ansi_codes(line).as_str())
+}
+
+/// Wraps text at word boundaries, so that no line is wider than the given width.
+/// Color codes take no room, and the colors in effect at a break are carried over to the next line.
+/// Words that are wider than a line are broken.
+/// # Arguments
+/// * `text` - The text to wrap. Every line is wrapped on its own.
+/// * `width` - The number of columns a line may take.
+/// * `indent` - The text the continuation lines start with, such as the spaces under a list bullet.
+/// # Returns
+/// The wrapped text.
+pub fn wrap_text(text: &str, width: usize, indent: &str) -> String {
+    let indent_width = visible_width(indent);
+    // At least one character fits on every line
+    let width = width.max(indent_width + 1);
+
+    text.split('\n')
+        .map(|line| {
+            if visible_width(line) <= width {
+                return line.to_string();
+            }
+            wrap_line(line, width, indent, indent_width)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn wrap_line(line: &str, width: usize, indent: &str, indent_width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    // Whether the current line has a word on it, it is only broken after one
+    let mut has_word = false;
+    // The color codes in effect, since the last reset
+    let mut active: Vec<&str> = Vec::new();
+
+    let mut break_line = |current: &mut String, current_width: &mut usize, active: &[&str]| {
+        if !active.is_empty() {
+            current.push_str("\x1b[0m");
+        }
+        lines.push(std::mem::take(current));
+        current.push_str(indent);
+        current.push_str(&active.concat());
+        *current_width = indent_width;
+    };
+
+    for (i, word) in line.split(' ').enumerate() {
+        let word_width = visible_width(word);
+        if i > 0 {
+            if has_word && word_width > 0 && current_width + 1 + word_width > width {
+                break_line(&mut current, &mut current_width, &active);
+                has_word = false;
+            } else {
+                current.push(' ');
+                current_width += 1;
+            }
+        }
+
+        // A word that does not fit on a line of its own is broken wherever the line is full
+        let fits = indent_width + word_width <= width;
+        for token in tokenize(word) {
+            match token {
+                Token::Code(code) => {
+                    if code == "\x1b[0m" || code == "\x1b[m" {
+                        active.clear();
+                    } else {
+                        active.push(code);
+                    }
+                    current.push_str(code);
+                }
+                Token::Char(c) => {
+                    let char_width = c.width().unwrap_or(0);
+                    if !fits && has_word && current_width + char_width > width {
+                        break_line(&mut current, &mut current_width, &active);
+                    }
+                    current.push(c);
+                    current_width += char_width;
+                    has_word = true;
+                }
+            }
+        }
+    }
+    lines.push(current);
+
+    lines.join("\n")
+}
+
+/// Cuts a line that is wider than the given width, and ends it with a marker.
+/// Color codes take no room, and are reset before the marker.
+/// # Arguments
+/// * `line` - The line to clip.
+/// * `width` - The number of columns the line may take, including the marker.
+/// * `marker` - The text that shows the line was clipped.
+/// # Returns
+/// The clipped line, or the line itself if it fits.
+pub fn clip_line(line: &str, width: usize, marker: &str) -> String {
+    if visible_width(line) <= width {
+        return line.to_string();
+    }
+    let room = width.saturating_sub(visible_width(marker));
+
+    let mut clipped = String::new();
+    let mut used = 0;
+    for token in tokenize(line) {
+        match token {
+            Token::Code(code) => clipped.push_str(code),
+            Token::Char(c) => {
+                let char_width = c.width().unwrap_or(0);
+                if used + char_width > room {
+                    break;
+                }
+                clipped.push(c);
+                used += char_width;
+            }
+        }
+    }
+    clipped.push_str("\x1b[0m");
+    clipped.push_str(marker);
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_width_of_colors_and_wide_chars() {
+        assert_eq!(visible_width("\x1b[31mred\x1b[0m"), 3);
+        assert_eq!(visible_width("日本"), 4);
+        assert_eq!(visible_width("\x1b[1;32m✔\x1b[0m done"), 6);
+    }
+
+    #[test]
+    fn wrap_at_word_boundaries() {
+        assert_eq!(wrap_text("one two three", 7, ""), "one two\nthree");
+        assert_eq!(wrap_text("one two\nthree", 20, ""), "one two\nthree");
+    }
+
+    #[test]
+    fn wrap_indents_continuation_lines() {
+        assert_eq!(
+            wrap_line("- one two three", 9, "  ", 2),
+            "- one two\n  three"
+        );
+    }
+
+    #[test]
+    fn wrap_wide_chars() {
+        assert_eq!(wrap_text("日本語 日本", 6, ""), "日本語\n日本");
+        assert_eq!(wrap_text("日本語", 4, ""), "日本\n語");
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        assert_eq!(wrap_text("abcdefghij", 4, ""), "abcd\nefgh\nij");
+        assert_eq!(wrap_text("a abcdefgh", 4, ""), "a\nabcd\nefgh");
+    }
+
+    #[test]
+    fn wrap_carries_colors_over() {
+        assert_eq!(
+            wrap_text("\x1b[31mone two\x1b[0m", 3, ""),
+            "\x1b[31mone\x1b[0m\n\x1b[31mtwo\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn clip_long_lines() {
+        assert_eq!(clip_line("abc", 3, "…"), "abc");
+        assert_eq!(clip_line("abcdefgh", 5, "…"), "abcd\x1b[0m…");
+        assert_eq!(clip_line("日本語", 4, "…"), "日\x1b[0m…");
+        assert_eq!(
+            clip_line("\x1b[32mabcdef\x1b[0m", 4, "…"),
+            "\x1b[32mabc\x1b[0m…"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+/// The watcher polls the files of a deck for changes.
+/// It keeps the modification time of every watched file, and calls back with the path of every
+/// file that was added, modified or removed since the last poll.
+/// A project directory is watched as a whole, while a deck presented from a file of a larger
+/// directory, such as the home directory, only has its own files watched.
+/// Polling keeps the watcher portable.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the project directory is polled for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The directories that are not watched, they hold build output and dependencies rather than the deck.
+const SKIPPED_DIRS: [&str; 2] = ["target", "node_modules"];
+
+/// What the watcher polls.
+pub enum Watched {
+    /// Every file of a project directory, recursively.
+    Dir(PathBuf),
+    /// The files listed by the function, which is called again after every change,
+    /// so that the list follows the deck as it is edited.
+    Files(Box<dyn Fn() -> Vec<PathBuf> + Send>),
+}
+
+/// Returns the modification time of every file in the directory, recursively.
+/// Hidden files and directories are skipped, and so are the build directories of code blocks.
+fn snapshot_dir(dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                if !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()) {
+                    snapshot_dir(&path, files);
+                }
+            }
+            Ok(metadata) => {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.insert(path, modified);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Returns the modification time of every listed file that exists.
+fn snapshot_files(paths: &[PathBuf], files: &mut HashMap<PathBuf, SystemTime>) {
+    for path in paths {
+        if let Ok(metadata) = fs::metadata(path) {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.insert(path.clone(), modified);
+        }
+    }
+}
+
+/// Watch the files of a deck for changes in a separate thread.
+/// # Arguments
+/// * `watched` - The directory or the files to watch.
+/// * `on_change` - Called with the path of every file that changed. The watcher stops when it returns false.
+pub fn watch<F>(watched: Watched, on_change: F) -> thread::JoinHandle<()>
+where
+    F: Fn(&Path) -> bool + Send + 'static,
+{
+    thread::Builder::new()
+        .name("watcher".to_string())
+        .spawn(move || {
+            let mut paths = match &watched {
+                Watched::Dir(_) => Vec::new(),
+                Watched::Files(list) => list(),
+            };
+            let snapshot =
+                |paths: &[PathBuf], files: &mut HashMap<PathBuf, SystemTime>| match &watched {
+                    Watched::Dir(dir) => snapshot_dir(dir, files),
+                    Watched::Files(_) => snapshot_files(paths, files),
+                };
+
+            let mut files = HashMap::new();
+            snapshot(&paths, &mut files);
+
+            loop {
+                thread::sleep(POLL_INTERVAL);
+
+                let mut current = HashMap::new();
+                snapshot(&paths, &mut current);
+
+                let mut changed: Vec<&PathBuf> = current
+                    .iter()
+                    .filter(|(path, modified)| files.get(*path) != Some(modified))
+                    .map(|(path, _)| path)
+                    .collect();
+                changed.extend(files.keys().filter(|path| !current.contains_key(*path)));
+
+                let any_changed = !changed.is_empty();
+                for path in changed {
+                    if !on_change(path) {
+                        return;
+                    }
+                }
+
+                // A change to the deck may add or remove slides and images, the new files are
+                // taken as they are, without being reported as changed.
+                if let (true, Watched::Files(list)) = (any_changed, &watched) {
+                    paths = list();
+                    current.clear();
+                    snapshot(&paths, &mut current);
+                }
+                files = current;
+            }
+        })
+        .expect("Failed to spawn thread")
+}